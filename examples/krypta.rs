@@ -86,6 +86,7 @@ fn setup(
     camera_targets: Res<CameraTargets>,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    mut images: ResMut<Assets<Image>>,
 )
 {
     // Utility functions to compute Z coordinate for floor and ground objects.
@@ -293,10 +294,7 @@ fn setup(
         wall_atlas_cols * 4 + 0
     };
 
-    // Add walls with occluder component.
-    let occluder_data = LightOccluder2D {
-        h_size: block_size / 2.0,
-    };
+    // Add wall sprites, their occluders are a single tile grid.
     for (i, row) in walls_info.iter().enumerate() {
         for (j, cell) in row.iter().enumerate() {
             if *cell == 1 {
@@ -321,15 +319,24 @@ fn setup(
                             ),
                         ))
                         .insert(RenderLayers::from_layers(CAMERA_LAYER_WALLS))
-                        .insert(occluder_data)
                         .id(),
                 );
             }
         }
     }
+    // Grid rows go upwards, the map rows downwards.
+    let grid_size = UVec2::new(walls_info[0].len() as u32, walls_info.len() as u32);
+    let grid_cells = TileOccluderGrid2D::cells_image(grid_size, |cell| {
+        walls_info[(grid_size.y - 1 - cell.y) as usize][cell.x as usize] == 1
+    });
     commands
         .spawn((Transform::default(), Visibility::default()))
         .insert(Name::new("walls"))
+        .insert(TileOccluderGrid2D {
+            tile_size: block_size,
+            origin:    get_block_translation(walls_info.len() - 1, 0) - block_size / 2.0,
+            cells:     images.add(grid_cells),
+        })
         .add_children(&walls);
 
     // Add decorations.
@@ -389,12 +396,32 @@ fn setup(
                         },
                     ))
                     .insert(RenderLayers::from_layers(CAMERA_LAYER_OBJECTS))
-                    .insert(LightOccluder2D {
-                        h_size: Vec2::splat(2.0),
-                    })
+                    .insert(LightOccluder2D::from(Circle::new(0.5)))
                     .insert(Name::new("candle_1"))
                     .id(),
             );
+
+            // Flame of the candle, drawn by the emissive camera so it glows
+            // on top of the lit scene.
+            decorations.push(
+                commands
+                    .spawn((
+                        Visibility::default(),
+                        Transform {
+                            translation: Vec3::new(x, y + 14.0, get_object_z(y)),
+                            scale: Vec2::splat(4.0).extend(0.0),
+                            ..default()
+                        },
+                        Sprite {
+                            color: Color::srgb_u8(255, 170, 60),
+                            custom_size: Some(Vec2::new(1.0, 2.0)),
+                            ..default()
+                        },
+                    ))
+                    .insert(RenderLayers::from_layers(CAMERA_LAYER_EMISSIVE))
+                    .insert(Name::new("candle_1_flame"))
+                    .id(),
+            );
         }
 
         // Candle 2.
@@ -422,9 +449,7 @@ fn setup(
                         },
                     ))
                     .insert(RenderLayers::from_layers(CAMERA_LAYER_OBJECTS))
-                    .insert(LightOccluder2D {
-                        h_size: Vec2::splat(2.0),
-                    })
+                    .insert(LightOccluder2D::from(Circle::new(0.5)))
                     .insert(Name::new("candle_2"))
                     .id(),
             );
@@ -456,9 +481,7 @@ fn setup(
                         },
                     ))
                     .insert(RenderLayers::from_layers(CAMERA_LAYER_OBJECTS))
-                    .insert(LightOccluder2D {
-                        h_size: Vec2::splat(2.0),
-                    })
+                    .insert(LightOccluder2D::from(Circle::new(0.5)))
                     .insert(Name::new("candle_3"))
                     .id(),
             );
@@ -489,9 +512,7 @@ fn setup(
                         },
                     ))
                     .insert(RenderLayers::from_layers(CAMERA_LAYER_OBJECTS))
-                    .insert(LightOccluder2D {
-                        h_size: Vec2::splat(2.0),
-                    })
+                    .insert(LightOccluder2D::from(Circle::new(0.5)))
                     .insert(Name::new("candle_4"))
                     .id(),
             );
@@ -520,8 +541,9 @@ fn setup(
                         },
                     ))
                     .insert(RenderLayers::from_layers(CAMERA_LAYER_OBJECTS))
-                    .insert(LightOccluder2D {
-                        h_size: Vec2::new(72.8, 31.0),
+                    // Only the base of the tomb blocks light.
+                    .insert(OccluderFromSprite {
+                        scale: Vec2::new(0.76, 0.48),
                     })
                     .insert(Name::new("tomb_1"))
                     .id(),
@@ -551,8 +573,9 @@ fn setup(
                         },
                     ))
                     .insert(RenderLayers::from_layers(CAMERA_LAYER_OBJECTS))
-                    .insert(LightOccluder2D {
-                        h_size: Vec2::new(72.8, 31.0),
+                    // Only the base of the tomb blocks light.
+                    .insert(OccluderFromSprite {
+                        scale: Vec2::new(0.76, 0.48),
                     })
                     .insert(Name::new("tomb_1"))
                     .id(),
@@ -616,7 +639,7 @@ fn setup(
         };
 
         let base = OmniLightSource2D {
-            falloff: Vec3::new(50.0, 20.0, 0.05),
+            falloff: Vec3::new(50.0, 20.0, 0.05).into(),
             intensity: 10.0,
             ..default()
         };
@@ -732,10 +755,11 @@ fn setup(
             "outdoor_light_9",
             OmniLightSource2D {
                 intensity:          1.2,
-                falloff:            Vec3::new(50.0, 40.0, 0.03),
+                falloff:            Vec3::new(50.0, 40.0, 0.03).into(),
                 color:              Color::srgb_u8(0, 206, 94),
                 jitter_intensity:   0.7,
                 jitter_translation: 3.0,
+                ..default()
             },
         ));
 
@@ -746,10 +770,11 @@ fn setup(
             "outdoor_light_10",
             OmniLightSource2D {
                 intensity:          1.2,
-                falloff:            Vec3::new(50.0, 40.0, 0.03),
+                falloff:            Vec3::new(50.0, 40.0, 0.03).into(),
                 color:              Color::srgb_u8(0, 206, 94),
                 jitter_intensity:   0.7,
                 jitter_translation: 3.0,
+                ..default()
             },
         ));
     }
@@ -767,6 +792,8 @@ fn setup(
         .insert(Name::new("skylight_mask_1"))
         .insert(SkylightMask2D {
             h_size: Vec2::new(430.0, 330.0),
+            feather: 32.0,
+            ..default()
         });
     commands
         .spawn((
@@ -776,6 +803,8 @@ fn setup(
         .insert(Name::new("skylight_mask_2"))
         .insert(SkylightMask2D {
             h_size: Vec2::new(163.3, 156.1),
+            feather: 32.0,
+            ..default()
         });
 
     // Add skylight light.
@@ -802,7 +831,7 @@ fn setup(
         .insert(OmniLightSource2D {
             intensity: 10.0,
             color: Color::srgb_u8(254, 100, 34),
-            falloff: Vec3::new(50.0, 20.0, 0.05),
+            falloff: Vec3::new(50.0, 20.0, 0.05).into(),
             ..default()
         })
         .insert(RenderLayers::from_layers(ALL_LAYERS))
@@ -815,7 +844,7 @@ fn setup(
         ..OrthographicProjection::default_2d()
     };
 
-    // Setup separate camera for floor, walls and objects.
+    // Setup separate camera for floor, walls, objects and emissive sprites.
     commands
         .spawn((
             Camera2d,
@@ -852,12 +881,26 @@ fn setup(
                 target: RenderTarget::Image(camera_targets.objects_target.clone()),
                 ..default()
             },
-            projection,
+            projection.clone(),
             Name::new("objects_targets_camera"),
         ))
         .insert(SpriteCamera)
         .insert(ObjectsCamera)
         .insert(RenderLayers::from_layers(CAMERA_LAYER_OBJECTS));
+    commands
+        .spawn((
+            Camera2d,
+            Camera {
+                hdr: false,
+                target: RenderTarget::Image(camera_targets.emissive_target.clone()),
+                ..default()
+            },
+            projection,
+            Name::new("emissive_target_camera"),
+        ))
+        .insert(SpriteCamera)
+        .insert(EmissiveCamera)
+        .insert(RenderLayers::from_layers(CAMERA_LAYER_EMISSIVE));
 }
 
 fn system_control_mouse_light(
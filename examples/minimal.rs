@@ -36,6 +36,7 @@ fn setup(mut commands: Commands, camera_targets: Res<CameraTargets>)
             Visibility::default(),
             LightOccluder2D {
                 h_size: Vec2::new(40.0, 20.0),
+                ..default()
             },
         ))
         .id();
@@ -73,7 +74,7 @@ fn setup(mut commands: Commands, camera_targets: Res<CameraTargets>)
             OmniLightSource2D {
                 intensity: 1.0,
                 color: Color::srgb_u8(255, 0, 0),
-                falloff: Vec3::new(1.5, 10.0, 0.005),
+                falloff: Vec3::new(1.5, 10.0, 0.005).into(),
                 ..default()
             },
         ));
@@ -85,7 +86,7 @@ fn setup(mut commands: Commands, camera_targets: Res<CameraTargets>)
             OmniLightSource2D {
                 intensity: 1.0,
                 color: Color::srgb_u8(0, 0, 255),
-                falloff: Vec3::new(1.5, 10.0, 0.005),
+                falloff: Vec3::new(1.5, 10.0, 0.005).into(),
                 ..default()
             },
         ));
@@ -97,7 +98,7 @@ fn setup(mut commands: Commands, camera_targets: Res<CameraTargets>)
             OmniLightSource2D {
                 intensity: 1.0,
                 color: Color::srgb_u8(0, 255, 0),
-                falloff: Vec3::new(1.5, 10.0, 0.005),
+                falloff: Vec3::new(1.5, 10.0, 0.005).into(),
                 ..default()
             },
         ));
@@ -53,6 +53,7 @@ fn setup(mut commands: Commands, camera_targets: Res<CameraTargets>)
             Visibility::default(),
             LightOccluder2D {
                 h_size: Vec2::new(80.0, 40.0),
+                ..default()
             },
             Mover,
         ))
@@ -91,7 +92,7 @@ fn setup(mut commands: Commands, camera_targets: Res<CameraTargets>)
             OmniLightSource2D {
                 intensity: 10.0,
                 color: Color::srgb_u8(255, 255, 0),
-                falloff: Vec3::new(1.5, 10.0, 0.01),
+                falloff: Vec3::new(1.5, 10.0, 0.01).into(),
                 ..default()
             },
         ));
@@ -103,7 +104,7 @@ fn setup(mut commands: Commands, camera_targets: Res<CameraTargets>)
             OmniLightSource2D {
                 intensity: 10.0,
                 color: Color::srgb_u8(0, 255, 255),
-                falloff: Vec3::new(1.5, 10.0, 0.01),
+                falloff: Vec3::new(1.5, 10.0, 0.01).into(),
                 ..default()
             },
         ));
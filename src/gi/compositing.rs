@@ -2,6 +2,7 @@ use bevy::core_pipeline::bloom::Bloom;
 use bevy::pbr::{MAX_CASCADES_PER_LIGHT, MAX_DIRECTIONAL_LIGHTS};
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
+use bevy::render::extract_resource::ExtractResource;
 use bevy::render::mesh::MeshVertexBufferLayoutRef;
 use bevy::render::render_resource::{
     AsBindGroup,
@@ -44,6 +45,10 @@ pub struct PostProcessingMaterial {
     #[texture(6)]
     #[sampler(7)]
     irradiance_image:  Handle<Image>,
+
+    #[texture(8)]
+    #[sampler(9)]
+    emissive_image:    Handle<Image>,
 }
 
 impl PostProcessingMaterial
@@ -54,6 +59,7 @@ impl PostProcessingMaterial
             floor_image:      camera_targets.floor_target.clone(),
             walls_image:      camera_targets.walls_target.clone(),
             objects_image:    camera_targets.objects_target.clone(),
+            emissive_image:   camera_targets.emissive_target.clone(),
             irradiance_image: gi_targets_wrapper
                 .targets
                 .as_ref()
@@ -64,12 +70,16 @@ impl PostProcessingMaterial
     }
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource, ExtractResource, Clone, Default)]
 pub struct CameraTargets
 {
-    pub floor_target:   Handle<Image>,
-    pub walls_target:   Handle<Image>,
-    pub objects_target: Handle<Image>,
+    pub floor_target:    Handle<Image>,
+    pub walls_target:    Handle<Image>,
+    pub objects_target:  Handle<Image>,
+    /// Target for emissive sprites, injected as radiance into the light pass.
+    pub emissive_target: Handle<Image>,
+    /// Sprite occluder masks at the SDF resolution, covering the SDF extent.
+    pub occluder_target: Handle<Image>,
 }
 
 impl CameraTargets
@@ -129,23 +139,69 @@ impl CameraTargets
             ..default()
         };
 
+        let mut emissive_image = Image {
+            texture_descriptor: TextureDescriptor {
+                label:           Some("target_emissive"),
+                size:            target_size,
+                dimension:       TextureDimension::D2,
+                format:          TextureFormat::bevy_default(),
+                mip_level_count: 1,
+                sample_count:    1,
+                usage:           TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+                view_formats:    &[],
+            },
+            ..default()
+        };
+
+        let occluder_size = Extent3d {
+            width: sizes.sdf_target_usize.x,
+            height: sizes.sdf_target_usize.y,
+            ..default()
+        };
+
+        let mut occluder_image = Image {
+            texture_descriptor: TextureDescriptor {
+                label:           Some("target_occluder"),
+                size:            occluder_size,
+                dimension:       TextureDimension::D2,
+                format:          TextureFormat::bevy_default(),
+                mip_level_count: 1,
+                sample_count:    1,
+                usage:           TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+                view_formats:    &[],
+            },
+            ..default()
+        };
+
         // Fill images data with zeroes.
         floor_image.resize(target_size);
         walls_image.resize(target_size);
         objects_image.resize(target_size);
+        emissive_image.resize(target_size);
+        occluder_image.resize(occluder_size);
 
         let floor_image_handle: Handle<Image> = Handle::weak_from_u128(9127312736151891273);
         let walls_image_handle: Handle<Image> = Handle::weak_from_u128(7264512947825624361);
         let objects_image_handle: Handle<Image> = Handle::weak_from_u128(2987462343287146234);
+        let emissive_image_handle: Handle<Image> = Handle::weak_from_u128(6108532876401263817);
+        let occluder_image_handle: Handle<Image> = Handle::weak_from_u128(4471920385716203948);
 
         images.insert(floor_image_handle.id(), floor_image);
         images.insert(walls_image_handle.id(), walls_image);
         images.insert(objects_image_handle.id(), objects_image);
+        images.insert(emissive_image_handle.id(), emissive_image);
+        images.insert(occluder_image_handle.id(), occluder_image);
 
         Self {
-            floor_target:   floor_image_handle,
-            walls_target:   walls_image_handle,
-            objects_target: objects_image_handle,
+            floor_target:    floor_image_handle,
+            walls_target:    walls_image_handle,
+            objects_target:  objects_image_handle,
+            emissive_target: emissive_image_handle,
+            occluder_target: occluder_image_handle,
         }
     }
 }
@@ -1,9 +1,37 @@
 use bevy::prelude::*;
 
 use crate::gi::compositing::PostProcessingMaterial;
+use crate::gi::occluder_proxy::OccluderShapeMaterial;
 
 pub const GI_SCREEN_PROBE_SIZE: i32 = 8;
 
+/// Extent of the SDF relative to the camera view, occluders slightly off
+/// screen still cast shadows into it.
+pub const SDF_VIEW_SCALE: f32 = 2.0;
+
 pub const POST_PROCESSING_RECT: Handle<Mesh> = Handle::weak_from_u128(23475629871623176235);
 pub const POST_PROCESSING_MATERIAL: Handle<PostProcessingMaterial> =
     Handle::weak_from_u128(52374048672736472871);
+
+pub const LIGHT_COOKIE_ATLAS: Handle<Image> = Handle::weak_from_u128(1843267011937465129);
+pub const LIGHT_COOKIE_SIZE: u32 = 128;
+pub const LIGHT_COOKIE_MAX_LAYERS: usize = 64;
+
+/// Lights binned per tile of probes, lights past it are dropped from the tile.
+pub const LIGHT_TILE_MAX_LIGHTS: u32 = 127;
+/// Tile entry count in `u32`, the light count followed by the light list.
+pub const LIGHT_TILE_STRIDE: u32 = LIGHT_TILE_MAX_LIGHTS + 1;
+
+/// SDF texels per side of the tiles occluders are binned into.
+pub const OCCLUDER_TILE_SIZE: u32 = 32;
+
+/// Extent of the static occluder SDF cache relative to the SDF, the view can
+/// move by a quarter of the SDF extent before the cache is rebuilt.
+pub const STATIC_SDF_SCALE: f32 = 1.5;
+
+/// Unit quad stretched over sprite and shape occluders on the occluder layer.
+pub const OCCLUDER_PROXY_RECT: Handle<Mesh> = Handle::weak_from_u128(7320184561947302816);
+
+/// Material of occluders rasterised from their own geometry.
+pub const OCCLUDER_FILL_MATERIAL: Handle<OccluderShapeMaterial> =
+    Handle::weak_from_u128(8803172654019283746);
@@ -0,0 +1,206 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+/// Frequency (Hz) of the noise driving `jitter_*` fields of lights.
+pub const LIGHT_JITTER_FREQUENCY: f32 = 12.0;
+
+/// Scalar signal in `[-1, 1]` driving one animated property of a light.
+#[derive(Reflect, Clone, Debug)]
+pub enum LightCurve
+{
+    /// Smooth value noise, the same seed always yields the same signal.
+    Noise
+    {
+        frequency: f32
+    },
+    /// Sine wave.
+    Pulse
+    {
+        frequency: f32, phase: f32
+    },
+    /// Square wave, `1` during `duty` part of the period and `-1` otherwise.
+    Strobe
+    {
+        frequency: f32, duty: f32
+    },
+    /// `(time, value)` keys sorted by time, interpolated linearly and looped
+    /// after the last key.
+    Keyframes(Vec<(f32, f32)>),
+}
+
+impl Default for LightCurve
+{
+    fn default() -> Self
+    {
+        Self::Noise {
+            frequency: LIGHT_JITTER_FREQUENCY,
+        }
+    }
+}
+
+impl LightCurve
+{
+    pub fn sample(&self, seed: u32, time: f32) -> f32
+    {
+        match self {
+            LightCurve::Noise { frequency } => value_noise(seed, time * frequency),
+            LightCurve::Pulse { frequency, phase } => (TAU * (time * frequency + phase)).sin(),
+            LightCurve::Strobe { frequency, duty } => {
+                if (time * frequency).rem_euclid(1.0) < *duty {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LightCurve::Keyframes(keys) => sample_keyframes(keys, time),
+        }
+    }
+}
+
+/// Curve scaled by an amplitude, used for intensity and offset.
+#[rustfmt::skip]
+#[derive(Reflect, Clone, Debug, Default)]
+pub struct LightTrack {
+    pub curve:     LightCurve,
+    pub amplitude: f32,
+}
+
+/// Curve blending the light color towards `color`: `-1` keeps the light
+/// color, `1` replaces it.
+#[rustfmt::skip]
+#[derive(Reflect, Clone, Debug, Default)]
+pub struct LightColorTrack {
+    pub curve: LightCurve,
+    pub color: Color,
+}
+
+/// Time driven animation of a light on the same entity. Evaluation only
+/// depends on the elapsed time and `seed`, so captures are reproducible.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct LightAnimation2D {
+    pub seed:      u32,
+    /// Added to the light intensity.
+    pub intensity: Option<LightTrack>,
+    pub color:     Option<LightColorTrack>,
+    /// Added to the light position, each axis is sampled with its own seed.
+    pub offset:    Option<LightTrack>,
+}
+
+impl LightAnimation2D
+{
+    /// Noise flicker matching `jitter_intensity` and `jitter_translation`.
+    pub fn jitter(seed: u32, intensity: f32, translation: f32) -> Self
+    {
+        Self {
+            seed,
+            intensity: (intensity != 0.0).then(|| LightTrack {
+                curve:     LightCurve::default(),
+                amplitude: intensity,
+            }),
+            color: None,
+            offset: (translation != 0.0).then(|| LightTrack {
+                curve:     LightCurve::default(),
+                amplitude: translation,
+            }),
+        }
+    }
+
+    pub fn animate(
+        &self,
+        time: f32,
+        intensity: f32,
+        color: Color,
+        center: Vec2,
+    ) -> (f32, Color, Vec2)
+    {
+        let intensity = match &self.intensity {
+            Some(track) => {
+                intensity + track.amplitude * track.curve.sample(self.seed ^ 0x68e3_1da4, time)
+            }
+            None => intensity,
+        };
+
+        let color = match &self.color {
+            Some(track) => {
+                let t = track.curve.sample(self.seed ^ 0x5bd1_e995, time) * 0.5 + 0.5;
+                LinearRgba::from(color)
+                    .mix(&LinearRgba::from(track.color), t.clamp(0.0, 1.0))
+                    .into()
+            }
+            None => color,
+        };
+
+        let center = match &self.offset {
+            Some(track) => {
+                center
+                    + track.amplitude
+                        * Vec2::new(
+                            track.curve.sample(self.seed ^ 0x1b87_3593, time),
+                            track.curve.sample(self.seed ^ 0xcc9e_2d51, time),
+                        )
+            }
+            None => center,
+        };
+
+        (intensity, color, center)
+    }
+}
+
+/// Seed for the jitter of a light, stable for the lifetime of the entity.
+pub fn entity_seed(entity: Entity) -> u32
+{
+    let bits = entity.to_bits();
+    let mut x = bits ^ (bits >> 33);
+    x = x.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    x ^= x >> 33;
+    x as u32
+}
+
+fn hash_to_signed_unit(seed: u32, i: i32) -> f32
+{
+    let mut x = (i as u32).wrapping_mul(0x9e37_79b1) ^ seed.wrapping_mul(0x85eb_ca77);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x2c1b_3c6d);
+    x ^= x >> 12;
+    x = x.wrapping_mul(0x297a_2d39);
+    x ^= x >> 15;
+    (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
+
+fn value_noise(seed: u32, t: f32) -> f32
+{
+    let i = t.floor();
+    let f = t - i;
+    let s = f * f * (3.0 - 2.0 * f);
+    let a = hash_to_signed_unit(seed, i as i32);
+    let b = hash_to_signed_unit(seed, i as i32 + 1);
+    a + (b - a) * s
+}
+
+fn sample_keyframes(keys: &[(f32, f32)], time: f32) -> f32
+{
+    let (Some(first), Some(last)) = (keys.first(), keys.last()) else {
+        return 0.0;
+    };
+    if last.0 <= 0.0 {
+        return first.1;
+    }
+
+    let t = time.rem_euclid(last.0);
+    if t <= first.0 {
+        return first.1;
+    }
+    for pair in keys.windows(2) {
+        let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+        if t <= t1 {
+            let k = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1.0 };
+            return v0 + (v1 - v0) * k;
+        }
+    }
+    last.1
+}
@@ -0,0 +1,81 @@
+use bevy::math::Vec3A;
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+
+use crate::gi::light_animation::LightAnimation2D;
+use crate::gi::types::{AreaLight2D, LineLight2D, OmniLightSource2D, SpotLight2D};
+
+/// Light whose reach is bounded by its falloff radius.
+pub trait LightInfluence: Component
+{
+    /// Half size of the region lit by the light, in the light local space.
+    fn influence_h_extent(&self) -> Vec2;
+}
+
+impl LightInfluence for OmniLightSource2D
+{
+    fn influence_h_extent(&self) -> Vec2
+    {
+        Vec2::splat(self.falloff.radius() + self.jitter_translation.abs())
+    }
+}
+
+impl LightInfluence for SpotLight2D
+{
+    fn influence_h_extent(&self) -> Vec2
+    {
+        Vec2::splat(self.falloff.radius())
+    }
+}
+
+impl LightInfluence for LineLight2D
+{
+    fn influence_h_extent(&self) -> Vec2
+    {
+        Vec2::new(self.half_length.max(0.0), 0.0) + self.falloff.radius()
+    }
+}
+
+impl LightInfluence for AreaLight2D
+{
+    fn influence_h_extent(&self) -> Vec2
+    {
+        self.h_size.max(Vec2::ZERO) + self.falloff.radius()
+    }
+}
+
+/// Keeps the [`Aabb`] of lights in sync with their influence, so that
+/// `check_visibility` culls lights that can't reach any view. Lights with an
+/// unbounded falloff have no [`Aabb`] and are never culled.
+#[allow(clippy::type_complexity)]
+pub fn system_update_light_bounds<T: LightInfluence>(
+    mut commands: Commands,
+    query: Query<
+        (Entity, &T, &GlobalTransform, Option<&LightAnimation2D>),
+        Or<(
+            Changed<T>,
+            Changed<GlobalTransform>,
+            Changed<LightAnimation2D>,
+        )>,
+    >,
+)
+{
+    for (entity, light, transform, animation) in query.iter() {
+        let mut h_extent = light.influence_h_extent();
+        if let Some(track) = animation.and_then(|animation| animation.offset.as_ref()) {
+            h_extent += track.amplitude.abs();
+        }
+
+        if !h_extent.is_finite() {
+            commands.entity(entity).remove::<Aabb>();
+            continue;
+        }
+
+        // Lights ignore scale, so undo it to keep the bounds in world units.
+        let scale = transform.scale().truncate().abs().max(Vec2::splat(1e-6));
+        commands.entity(entity).insert(Aabb {
+            center:       Vec3A::ZERO,
+            half_extents: (h_extent / scale).extend(0.0).into(),
+        });
+    }
+}
@@ -0,0 +1,145 @@
+use bevy::image::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{
+    Extent3d,
+    TextureDimension,
+    TextureFormat,
+    TextureViewDescriptor,
+    TextureViewDimension,
+};
+
+use crate::gi::constants::{LIGHT_COOKIE_ATLAS, LIGHT_COOKIE_MAX_LAYERS, LIGHT_COOKIE_SIZE};
+use crate::gi::types::LightCookie2D;
+
+/// Texture array holding every cookie used by lights, one cookie per layer.
+#[derive(Resource, ExtractResource, Clone)]
+pub struct LightCookieAtlas
+{
+    pub image:  Handle<Image>,
+    pub layers: Vec<AssetId<Image>>,
+}
+
+impl Default for LightCookieAtlas
+{
+    fn default() -> Self
+    {
+        Self {
+            image:  LIGHT_COOKIE_ATLAS,
+            layers: vec![],
+        }
+    }
+}
+
+impl LightCookieAtlas
+{
+    pub fn layer(&self, image: AssetId<Image>) -> Option<u32>
+    {
+        self.layers
+            .iter()
+            .position(|id| *id == image)
+            .map(|i| i as u32)
+    }
+}
+
+#[rustfmt::skip]
+fn create_cookie_array(size: u32, data: Vec<u8>) -> Image {
+    let layers = (data.len() / (size * size * 4) as usize) as u32;
+    let mut image = Image::new(
+        Extent3d {
+            width:                 size,
+            height:                size,
+            depth_or_array_layers: layers,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..default()
+    });
+
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        mag_filter:     ImageFilterMode::Linear,
+        min_filter:     ImageFilterMode::Linear,
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::ClampToEdge,
+        address_mode_w: ImageAddressMode::ClampToEdge,
+        ..default()
+    });
+
+    image
+}
+
+#[rustfmt::skip]
+pub(crate) fn system_setup_light_cookie_atlas(
+    mut images: ResMut<Assets<Image>>,
+    atlas:      Res<LightCookieAtlas>,
+) {
+    // Single white layer, so the binding is valid while no cookies are used.
+    images.insert(atlas.image.id(), create_cookie_array(1, vec![255; 4]));
+}
+
+#[rustfmt::skip]
+pub(crate) fn system_update_light_cookie_atlas(
+        query_cookies: Query<&LightCookie2D>,
+    mut image_events:  EventReader<AssetEvent<Image>>,
+    mut images:        ResMut<Assets<Image>>,
+    mut atlas:         ResMut<LightCookieAtlas>,
+) {
+    // Keep layers of cookies that are still used in place, append new ones.
+    let mut layers: Vec<AssetId<Image>> = atlas
+        .layers
+        .iter()
+        .copied()
+        .filter(|id| query_cookies.iter().any(|cookie| cookie.image.id() == *id))
+        .collect();
+    for cookie in query_cookies.iter() {
+        let id = cookie.image.id();
+        if !layers.contains(&id) && images.contains(id) {
+            layers.push(id);
+        }
+    }
+    if layers.len() > LIGHT_COOKIE_MAX_LAYERS {
+        warn!("Too many light cookies, only {} are supported", LIGHT_COOKIE_MAX_LAYERS);
+        layers.truncate(LIGHT_COOKIE_MAX_LAYERS);
+    }
+
+    let modified = image_events.read().any(|event| match event {
+        AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => layers.contains(id),
+        _ => false,
+    });
+    if !modified && layers == atlas.layers {
+        return;
+    }
+
+    let image = if layers.is_empty() {
+        create_cookie_array(1, vec![255; 4])
+    } else {
+        let size = LIGHT_COOKIE_SIZE;
+        let mut data = Vec::with_capacity((size * size * 4) as usize * layers.len());
+        for id in layers.iter() {
+            let Some(cookie) = images.get(*id) else {
+                data.extend(std::iter::repeat_n(255, (size * size * 4) as usize));
+                continue;
+            };
+            // Nearest-neighbour resample of the cookie into the layer.
+            for y in 0..size {
+                for x in 0..size {
+                    let color = cookie
+                        .get_color_at(x * cookie.width() / size, y * cookie.height() / size)
+                        .unwrap_or(Color::WHITE);
+                    data.extend_from_slice(&color.to_srgba().to_u8_array());
+                }
+            }
+        }
+        create_cookie_array(size, data)
+    };
+
+    images.insert(atlas.image.id(), image);
+    atlas.layers = layers;
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{PrimitiveTopology, VertexAttributeValues};
+use bevy::utils::{HashMap, HashSet};
+
+use crate::gi::types::MeshOccluder2D;
+use crate::gi::util;
+
+/// Outlines of the meshes of [`MeshOccluder2D`], in the mesh local space.
+#[rustfmt::skip]
+#[derive(Default, Resource)]
+pub struct MeshOccluderOutlines {
+    outlines: HashMap<AssetId<Mesh>, Vec<Vec<Vec2>>>,
+}
+
+impl MeshOccluderOutlines
+{
+    /// Contours of the mesh outline, `None` until the mesh is loaded.
+    pub fn get(&self, mesh: &Mesh2d) -> Option<&[Vec<Vec2>]>
+    {
+        self.outlines.get(&mesh.id()).map(Vec::as_slice)
+    }
+}
+
+/// Outline of a triangle list, other topologies have none.
+fn mesh_outline(mesh: &Mesh) -> Vec<Vec<Vec2>>
+{
+    let Some(positions) = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .and_then(VertexAttributeValues::as_float3)
+    else {
+        return vec![];
+    };
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return vec![];
+    }
+
+    let positions: Vec<Vec2> = positions.iter().map(|p| Vec2::new(p[0], p[1])).collect();
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+    let triangles: Vec<[usize; 3]> = indices
+        .chunks_exact(3)
+        .filter(|triangle| triangle.iter().all(|i| *i < positions.len()))
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect();
+
+    util::mesh_outlines(&positions, &triangles)
+}
+
+/// Extracts the outlines of meshes used by occluders, once per mesh asset, and
+/// forgets those of meshes no occluder uses any more.
+#[rustfmt::skip]
+pub fn system_update_mesh_occluder_outlines(
+    mut outlines:    ResMut<MeshOccluderOutlines>,
+        meshes:      Res<Assets<Mesh>>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+
+    query_occluders: Query<&Mesh2d, With<MeshOccluder2D>>,
+) {
+    let used = query_occluders.iter().map(|mesh| mesh.id()).collect::<HashSet<_>>();
+
+    // The static SDF cache is rebuilt when the outlines change, only flag
+    // actual edits.
+    let cached = &mut outlines.bypass_change_detection().outlines;
+    let count = cached.len();
+    for event in mesh_events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            cached.remove(id);
+        }
+    }
+    cached.retain(|id, _| used.contains(id));
+    let mut changed = cached.len() != count;
+
+    for id in used {
+        if cached.contains_key(&id) {
+            continue;
+        }
+        if let Some(mesh_asset) = meshes.get(id) {
+            cached.insert(id, mesh_outline(mesh_asset));
+            changed = true;
+        }
+    }
+
+    if changed {
+        outlines.set_changed();
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::atomic::Ordering;
+
 use bevy::asset::embedded_asset;
 use bevy::prelude::*;
 use bevy::render::extract_resource::ExtractResourcePlugin;
@@ -8,11 +10,35 @@ use bevy::render::view::{check_visibility, VisibilitySystems};
 use bevy::render::{Render, RenderApp, RenderSet};
 use bevy::sprite::Material2dPlugin;
 use bevy::window::{PrimaryWindow, WindowResized};
-use types::{LightOccluder2D, OmniLightSource2D};
+use types::{
+    AreaLight2D,
+    LightOccluder2D,
+    LineLight2D,
+    MeshOccluder2D,
+    OmniLightSource2D,
+    PolygonOccluder2D,
+    SpotLight2D,
+};
 
 use self::pipeline::GiTargets;
 use crate::gi::compositing::{setup_post_processing_camera, CameraTargets, PostProcessingMaterial};
 use crate::gi::constants::*;
+use crate::gi::light_bounds::system_update_light_bounds;
+use crate::gi::light_cookie::{
+    system_setup_light_cookie_atlas,
+    system_update_light_cookie_atlas,
+    LightCookieAtlas,
+};
+use crate::gi::mesh_occluder::{system_update_mesh_occluder_outlines, MeshOccluderOutlines};
+use crate::gi::occluder_proxy::{
+    jump_flood_enabled,
+    system_despawn_occluder_proxies,
+    system_setup_occluder_proxies,
+    system_sync_mesh_occluder_proxies,
+    system_sync_occluder_proxies,
+    system_sync_polygon_occluder_proxies,
+    OccluderShapeMaterial,
+};
 use crate::gi::pipeline::{
     system_queue_bind_groups,
     system_setup_gi_pipeline,
@@ -21,21 +47,38 @@ use crate::gi::pipeline::{
     LightPassPipelineBindGroups,
 };
 use crate::gi::pipeline_assets::{
+    system_extract_occluders,
     system_extract_pipeline_assets,
+    system_extract_static_occluders,
     system_load_embedded_shader_dependencies,
     system_prepare_pipeline_assets,
     EmbeddedShaderDependencies,
     LightPassPipelineAssets,
 };
-use crate::gi::resource::ComputedTargetSizes;
+use crate::gi::resource::{ComputedTargetSizes, SdfMode};
+use crate::gi::sprite_occluder::{
+    system_setup_sprite_occluders,
+    system_sync_occluder_camera,
+    system_sync_occluders_from_sprite,
+    system_sync_sprite_occluders,
+    SpriteOccluderMaterial,
+};
+use crate::gi::tile_occluder::{system_sync_tile_occluders, TileOccluderMaterial};
 use crate::prelude::BevyMagicLight2DSettings;
 
 mod constants;
+mod light_bounds;
+mod light_cookie;
+mod mesh_occluder;
+mod occluder_proxy;
 mod pipeline;
 mod pipeline_assets;
+mod sprite_occluder;
+mod tile_occluder;
 mod types_gpu;
 
 pub mod compositing;
+pub mod light_animation;
 pub mod render_layer;
 pub mod resource;
 pub mod types;
@@ -54,13 +97,20 @@ impl Plugin for BevyMagicLight2DPlugin
     {
         app.add_plugins((
             ExtractResourcePlugin::<GiTargetsWrapper>::default(),
+            ExtractResourcePlugin::<CameraTargets>::default(),
+            ExtractResourcePlugin::<LightCookieAtlas>::default(),
             Material2dPlugin::<PostProcessingMaterial>::default(),
+            Material2dPlugin::<SpriteOccluderMaterial>::default(),
+            Material2dPlugin::<TileOccluderMaterial>::default(),
+            Material2dPlugin::<OccluderShapeMaterial>::default(),
         ))
         .init_resource::<CameraTargets>()
         .init_resource::<GiTargetsWrapper>()
         .init_resource::<BevyMagicLight2DSettings>()
         .init_resource::<ComputedTargetSizes>()
         .init_resource::<EmbeddedShaderDependencies>()
+        .init_resource::<LightCookieAtlas>()
+        .init_resource::<MeshOccluderOutlines>()
         .add_systems(
             PreStartup,
             (
@@ -68,34 +118,98 @@ impl Plugin for BevyMagicLight2DPlugin
                 detect_target_sizes,
                 system_setup_gi_pipeline.after(detect_target_sizes),
                 setup_post_processing_camera.after(system_setup_gi_pipeline),
+                system_setup_sprite_occluders.after(setup_post_processing_camera),
+                system_setup_light_cookie_atlas,
+                system_setup_occluder_proxies,
             )
                 .chain(),
         )
         .add_systems(PreUpdate, handle_window_resize)
+        .add_systems(
+            PostUpdate,
+            (
+                system_update_light_cookie_atlas,
+                system_update_mesh_occluder_outlines,
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                system_sync_sprite_occluders.before(TransformSystem::TransformPropagate),
+                system_sync_tile_occluders.before(TransformSystem::TransformPropagate),
+                (
+                    system_sync_occluders_from_sprite,
+                    system_despawn_occluder_proxies::<LightOccluder2D>,
+                    system_despawn_occluder_proxies::<PolygonOccluder2D>,
+                    system_despawn_occluder_proxies::<MeshOccluder2D>,
+                    (
+                        system_sync_occluder_proxies,
+                        system_sync_polygon_occluder_proxies,
+                        system_sync_mesh_occluder_proxies,
+                    )
+                        .run_if(jump_flood_enabled),
+                )
+                    .chain()
+                    .before(TransformSystem::TransformPropagate)
+                    .before(VisibilitySystems::CalculateBounds),
+                system_sync_occluder_camera
+                    .after(TransformSystem::TransformPropagate)
+                    .before(VisibilitySystems::UpdateFrusta),
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                system_update_light_bounds::<OmniLightSource2D>,
+                system_update_light_bounds::<SpotLight2D>,
+                system_update_light_bounds::<LineLight2D>,
+                system_update_light_bounds::<AreaLight2D>,
+            )
+                .in_set(VisibilitySystems::CalculateBounds),
+        )
         .add_systems(
             PostUpdate,
             (
                 check_visibility::<With<OmniLightSource2D>>,
+                check_visibility::<With<SpotLight2D>>,
+                check_visibility::<With<LineLight2D>>,
+                check_visibility::<With<AreaLight2D>>,
                 check_visibility::<With<LightOccluder2D>>,
+                check_visibility::<With<PolygonOccluder2D>>,
             )
                 .in_set(VisibilitySystems::CheckVisibility),
         );
         embedded_asset!(app, "shaders/gi_attenuation.wgsl");
         embedded_asset!(app, "shaders/gi_camera.wgsl");
         embedded_asset!(app, "shaders/gi_halton.wgsl");
+        embedded_asset!(app, "shaders/gi_jfa_resolve.wgsl");
+        embedded_asset!(app, "shaders/gi_jfa_seed.wgsl");
+        embedded_asset!(app, "shaders/gi_jfa_step.wgsl");
+        embedded_asset!(app, "shaders/gi_light_binning.wgsl");
         embedded_asset!(app, "shaders/gi_math.wgsl");
+        embedded_asset!(app, "shaders/gi_occluder_shape.wgsl");
+        embedded_asset!(app, "shaders/gi_occluder_shapes.wgsl");
         embedded_asset!(app, "shaders/gi_post_processing.wgsl");
         embedded_asset!(app, "shaders/gi_raymarch.wgsl");
         embedded_asset!(app, "shaders/gi_sdf.wgsl");
+        embedded_asset!(app, "shaders/gi_sprite_occluder.wgsl");
         embedded_asset!(app, "shaders/gi_ss_blend.wgsl");
         embedded_asset!(app, "shaders/gi_ss_bounce.wgsl");
         embedded_asset!(app, "shaders/gi_ss_filter.wgsl");
         embedded_asset!(app, "shaders/gi_ss_probe.wgsl");
+        embedded_asset!(app, "shaders/gi_tile_occluder.wgsl");
         embedded_asset!(app, "shaders/gi_types.wgsl");
 
         let render_app = app.sub_app_mut(RenderApp);
         render_app
-            .add_systems(ExtractSchedule, system_extract_pipeline_assets)
+            .add_systems(
+                ExtractSchedule,
+                (
+                    system_extract_pipeline_assets,
+                    system_extract_occluders,
+                    system_extract_static_occluders,
+                ),
+            )
             .add_systems(
                 Render,
                 (
@@ -163,7 +277,10 @@ pub fn handle_window_resize(
             PostProcessingMaterial::create(&res_camera_targets, &res_gi_targets_wrapper),
         );
 
-        *res_gi_targets_wrapper = GiTargetsWrapper{targets: Some(GiTargets::create(&mut assets_image, &res_target_sizes))};
+        *res_gi_targets_wrapper = GiTargetsWrapper{
+            targets:    Some(GiTargets::create(&mut assets_image, &res_target_sizes)),
+            generation: res_gi_targets_wrapper.generation.wrapping_add(1),
+        };
         *res_camera_targets = CameraTargets::create(&mut assets_image, &res_target_sizes);
     }
 }
@@ -194,16 +311,27 @@ impl render_graph::Node for LightPass2DNode
         if let Some(pipeline_bind_groups) = world.get_resource::<LightPassPipelineBindGroups>() {
             let pipeline_cache = world.resource::<PipelineCache>();
             let pipeline = world.resource::<LightPassPipeline>();
+            let pipeline_assets = world.resource::<LightPassPipelineAssets>();
             let target_sizes = world.resource::<ComputedTargetSizes>();
 
             if let (
                 Some(sdf_pipeline),
+                Some(static_sdf_pipeline),
+                Some(jfa_seed_pipeline),
+                Some(jfa_step_pipeline),
+                Some(jfa_resolve_pipeline),
+                Some(light_binning_pipeline),
                 Some(ss_probe_pipeline),
                 Some(ss_bounce_pipeline),
                 Some(ss_blend_pipeline),
                 Some(ss_filter_pipeline),
             ) = (
                 pipeline_cache.get_compute_pipeline(pipeline.sdf_pipeline),
+                pipeline_cache.get_compute_pipeline(pipeline.static_sdf_pipeline),
+                pipeline_cache.get_compute_pipeline(pipeline.jfa_seed_pipeline),
+                pipeline_cache.get_compute_pipeline(pipeline.jfa_step_pipeline),
+                pipeline_cache.get_compute_pipeline(pipeline.jfa_resolve_pipeline),
+                pipeline_cache.get_compute_pipeline(pipeline.light_binning_pipeline),
                 pipeline_cache.get_compute_pipeline(pipeline.ss_probe_pipeline),
                 pipeline_cache.get_compute_pipeline(pipeline.ss_bounce_pipeline),
                 pipeline_cache.get_compute_pipeline(pipeline.ss_blend_pipeline),
@@ -217,7 +345,17 @@ impl render_graph::Node for LightPass2DNode
                         .command_encoder()
                         .begin_compute_pass(&ComputePassDescriptor { label: Some("light_pass_2d"), ..default() });
 
+                if pipeline_assets.sdf_mode == SdfMode::Analytic
+                    && pipeline_assets.static_sdf_cache.dirty.swap(false, Ordering::Relaxed)
                 {
+                    let grid_w = target_sizes.static_sdf_target_usize.x / WORKGROUP_SIZE;
+                    let grid_h = target_sizes.static_sdf_target_usize.y / WORKGROUP_SIZE;
+                    pass.set_bind_group(0, &pipeline_bind_groups.static_sdf_bind_group, &[]);
+                    pass.set_pipeline(static_sdf_pipeline);
+                    pass.dispatch_workgroups(grid_w, grid_h, 1);
+                }
+
+                if pipeline_assets.sdf_mode == SdfMode::Analytic {
                     let grid_w = sdf_w / WORKGROUP_SIZE;
                     let grid_h = sdf_h / WORKGROUP_SIZE;
                     pass.set_bind_group(0, &pipeline_bind_groups.sdf_bind_group, &[]);
@@ -225,6 +363,32 @@ impl render_graph::Node for LightPass2DNode
                     pass.dispatch_workgroups(grid_w, grid_h, 1);
                 }
 
+                if pipeline_assets.jfa_enabled {
+                    let grid_w = sdf_w / WORKGROUP_SIZE;
+                    let grid_h = sdf_h / WORKGROUP_SIZE;
+                    pass.set_bind_group(0, &pipeline_bind_groups.jfa_seed_bind_group, &[]);
+                    pass.set_pipeline(jfa_seed_pipeline);
+                    pass.dispatch_workgroups(grid_w, grid_h, 1);
+
+                    pass.set_pipeline(jfa_step_pipeline);
+                    for jfa_step_bind_group in pipeline_bind_groups.jfa_step_bind_groups.iter() {
+                        pass.set_bind_group(0, jfa_step_bind_group, &[]);
+                        pass.dispatch_workgroups(grid_w, grid_h, 1);
+                    }
+
+                    pass.set_bind_group(0, &pipeline_bind_groups.jfa_resolve_bind_group, &[]);
+                    pass.set_pipeline(jfa_resolve_pipeline);
+                    pass.dispatch_workgroups(grid_w, grid_h, 1);
+                }
+
+                {
+                    let grid_w = target_sizes.probe_grid_usize.x / WORKGROUP_SIZE;
+                    let grid_h = target_sizes.probe_grid_usize.y / WORKGROUP_SIZE;
+                    pass.set_bind_group(0, &pipeline_bind_groups.light_binning_bind_group, &[]);
+                    pass.set_pipeline(light_binning_pipeline);
+                    pass.dispatch_workgroups(grid_w, grid_h, 1);
+                }
+
                 {
                     let grid_w = target_sizes.probe_grid_usize.x / WORKGROUP_SIZE;
                     let grid_h = target_sizes.probe_grid_usize.y / WORKGROUP_SIZE;
@@ -0,0 +1,289 @@
+use std::marker::PhantomData;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::primitives::Aabb;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef, ShaderType};
+use bevy::render::view::RenderLayers;
+use bevy::sprite::Material2d;
+
+use crate::gi::constants::{OCCLUDER_FILL_MATERIAL, OCCLUDER_PROXY_RECT};
+use crate::gi::resource::{BevyMagicLight2DSettings, SdfMode};
+use crate::gi::types::{LightOccluder2D, MeshOccluder2D, OccluderOperation, PolygonOccluder2D};
+use crate::gi::types_gpu::{decompose_2d, GpuLightOccluder2D};
+use crate::gi::{render_layer, util};
+
+/// Shape id of [`OCCLUDER_FILL_MATERIAL`], must match `gi_occluder_shapes.wgsl`.
+const OCCLUDER_SHAPE_FILL: u32 = 4;
+
+/// Links an occluder of type `T` to the child rasterising it on the occluder
+/// layer when the SDF is built with [`SdfMode::JumpFlood`].
+#[derive(Component)]
+pub struct OccluderProxy<T: Component>
+{
+    entity: Entity,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component> OccluderProxy<T>
+{
+    fn new(entity: Entity) -> Self
+    {
+        Self {
+            entity,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Marks the child rasterising an occluder of type `T`, so that it can be
+/// found when its owner is despawned without its children.
+#[derive(Component)]
+pub struct OccluderProxyMesh<T: Component>(PhantomData<T>);
+
+impl<T: Component> Default for OccluderProxyMesh<T>
+{
+    fn default() -> Self
+    {
+        Self(PhantomData)
+    }
+}
+
+#[rustfmt::skip]
+#[derive(ShaderType, Clone, Copy, Default, PartialEq)]
+pub struct OccluderShapeParams {
+    pub h_extent: Vec2,
+    pub shape:    u32,
+    pub radius:   f32,
+}
+
+#[rustfmt::skip]
+#[derive(AsBindGroup, Clone, TypePath, Asset, Default)]
+pub struct OccluderShapeMaterial {
+    #[uniform(0)]
+    params: OccluderShapeParams,
+}
+
+impl Material2d for OccluderShapeMaterial
+{
+    fn fragment_shader() -> ShaderRef
+    {
+        "embedded://bevy_magic_light_2d/gi/shaders/gi_occluder_shape.wgsl".into()
+    }
+}
+
+impl OccluderShapeMaterial
+{
+    /// Material and quad transform of a shape occluder. The shape is sized in
+    /// world units like the analytic SDF, so the quad undoes the scale of the
+    /// occluder entity.
+    fn from_occluder(occluder: &LightOccluder2D, transform: &GlobalTransform) -> (Self, Transform)
+    {
+        let (scale, _, _) = decompose_2d(transform);
+        let scale = scale.max(Vec2::splat(1e-6));
+        let gpu_occluder = GpuLightOccluder2D::new(
+            occluder,
+            None,
+            &GlobalTransform::from_scale(scale.extend(1.0)),
+        );
+        let material = Self {
+            params: OccluderShapeParams {
+                h_extent: gpu_occluder.h_extent,
+                shape:    gpu_occluder.shape,
+                radius:   gpu_occluder.radius,
+            },
+        };
+
+        let transform = Transform::from_scale((gpu_occluder.h_extent * 2.0 / scale).extend(1.0));
+
+        (material, transform)
+    }
+}
+
+fn polygon_mesh(vertices: &[Vec2]) -> Mesh
+{
+    let positions: Vec<[f32; 3]> = vertices.iter().map(|v| [v.x, v.y, 0.0]).collect();
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0.0, 0.0, 1.0]; positions.len()],
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_indices(Indices::U32(util::triangulate_polygon(vertices)))
+}
+
+/// Subtractive occluders can't be carved out of the mask, their proxies stay
+/// hidden.
+fn proxy_visibility(operation: OccluderOperation) -> Visibility
+{
+    match operation {
+        OccluderOperation::Subtract { .. } => Visibility::Hidden,
+        _ => Visibility::Inherited,
+    }
+}
+
+/// Run condition for systems only needed when the SDF is built by jump flooding.
+pub fn jump_flood_enabled(settings: Res<BevyMagicLight2DSettings>) -> bool
+{
+    settings.sdf_mode == SdfMode::JumpFlood
+}
+
+#[rustfmt::skip]
+pub fn system_setup_occluder_proxies(
+    mut materials: ResMut<Assets<OccluderShapeMaterial>>,
+) {
+    materials.insert(OCCLUDER_FILL_MATERIAL.id(), OccluderShapeMaterial {
+        params: OccluderShapeParams {
+            shape: OCCLUDER_SHAPE_FILL,
+            ..default()
+        },
+    });
+}
+
+/// Despawns proxies of removed or despawned occluders, and all of them when the
+/// SDF is no longer built with jump flooding.
+#[rustfmt::skip]
+pub fn system_despawn_occluder_proxies<T: Component>(
+    mut commands:      Commands,
+        settings:      Res<BevyMagicLight2DSettings>,
+        query_owners:  Query<(Entity, &OccluderProxy<T>, Has<T>)>,
+        query_proxies: Query<(Entity, &Parent), With<OccluderProxyMesh<T>>>,
+) {
+    for (entity, proxy, has_occluder) in query_owners.iter() {
+        if settings.sdf_mode != SdfMode::JumpFlood || !has_occluder {
+            commands.entity(proxy.entity).despawn_recursive();
+            commands.entity(entity).remove::<OccluderProxy<T>>();
+        }
+    }
+
+    // Owners despawned without their children leave their proxy behind.
+    for (entity, parent) in query_proxies.iter() {
+        if !query_owners.contains(parent.get()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+pub fn system_sync_occluder_proxies(
+    mut commands:  Commands,
+    mut materials: ResMut<Assets<OccluderShapeMaterial>>,
+
+        query_occluders: Query<
+            (Entity, &LightOccluder2D, &GlobalTransform, Option<&OccluderProxy<LightOccluder2D>>),
+            Or<(Changed<LightOccluder2D>, Changed<GlobalTransform>, Without<OccluderProxy<LightOccluder2D>>)>,
+        >,
+    mut query_proxies:   Query<(&MeshMaterial2d<OccluderShapeMaterial>, &mut Transform, &mut Visibility)>,
+) {
+    for (entity, occluder, global_transform, proxy) in query_occluders.iter() {
+        let (new_material, new_transform) = OccluderShapeMaterial::from_occluder(occluder, global_transform);
+
+        let Some(proxy) = proxy else {
+            let proxy = commands
+                .spawn((
+                    Mesh2d(OCCLUDER_PROXY_RECT.clone()),
+                    MeshMaterial2d(materials.add(new_material)),
+                    new_transform,
+                    proxy_visibility(occluder.operation),
+                    RenderLayers::from_layers(render_layer::CAMERA_LAYER_OCCLUDER),
+                    OccluderProxyMesh::<LightOccluder2D>::default(),
+                ))
+                .set_parent(entity)
+                .id();
+            commands.entity(entity).insert(OccluderProxy::<LightOccluder2D>::new(proxy));
+            continue;
+        };
+
+        let Ok((material, mut transform, mut visibility)) = query_proxies.get_mut(proxy.entity) else {
+            continue;
+        };
+        if materials.get(&material.0).is_none_or(|old| old.params != new_material.params) {
+            materials.insert(&material.0, new_material);
+        }
+        transform.set_if_neq(new_transform);
+        visibility.set_if_neq(proxy_visibility(occluder.operation));
+    }
+}
+
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+pub fn system_sync_polygon_occluder_proxies(
+    mut commands: Commands,
+    mut meshes:   ResMut<Assets<Mesh>>,
+
+        query_polygons: Query<
+            (Entity, &PolygonOccluder2D, Option<&OccluderProxy<PolygonOccluder2D>>),
+            Or<(Changed<PolygonOccluder2D>, Without<OccluderProxy<PolygonOccluder2D>>)>,
+        >,
+    mut query_proxies:  Query<(&Mesh2d, &mut Visibility)>,
+) {
+    for (entity, polygon, proxy) in query_polygons.iter() {
+        let mesh = polygon_mesh(&polygon.vertices);
+
+        let Some(proxy) = proxy else {
+            let proxy = commands
+                .spawn((
+                    Mesh2d(meshes.add(mesh)),
+                    MeshMaterial2d(OCCLUDER_FILL_MATERIAL.clone()),
+                    Transform::default(),
+                    proxy_visibility(polygon.operation),
+                    RenderLayers::from_layers(render_layer::CAMERA_LAYER_OCCLUDER),
+                    OccluderProxyMesh::<PolygonOccluder2D>::default(),
+                ))
+                .set_parent(entity)
+                .id();
+            commands.entity(entity).insert(OccluderProxy::<PolygonOccluder2D>::new(proxy));
+            continue;
+        };
+
+        if let Ok((mesh_handle, mut visibility)) = query_proxies.get_mut(proxy.entity) {
+            meshes.insert(&mesh_handle.0, mesh);
+            visibility.set_if_neq(proxy_visibility(polygon.operation));
+            // Bounds are only computed for meshes without them.
+            commands.entity(proxy.entity).remove::<Aabb>();
+        }
+    }
+}
+
+/// Mesh occluders are rasterised from their own mesh.
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+pub fn system_sync_mesh_occluder_proxies(
+    mut commands: Commands,
+
+        query_occluders: Query<
+            (Entity, &MeshOccluder2D, &Mesh2d, Option<&OccluderProxy<MeshOccluder2D>>),
+            Or<(Changed<MeshOccluder2D>, Changed<Mesh2d>, Without<OccluderProxy<MeshOccluder2D>>)>,
+        >,
+    mut query_proxies:   Query<(&mut Mesh2d, &mut Visibility), Without<MeshOccluder2D>>,
+) {
+    for (entity, occluder, mesh, proxy) in query_occluders.iter() {
+        let Some(proxy) = proxy else {
+            let proxy = commands
+                .spawn((
+                    mesh.clone(),
+                    MeshMaterial2d(OCCLUDER_FILL_MATERIAL.clone()),
+                    Transform::default(),
+                    proxy_visibility(occluder.operation),
+                    RenderLayers::from_layers(render_layer::CAMERA_LAYER_OCCLUDER),
+                    OccluderProxyMesh::<MeshOccluder2D>::default(),
+                ))
+                .set_parent(entity)
+                .id();
+            commands.entity(entity).insert(OccluderProxy::<MeshOccluder2D>::new(proxy));
+            continue;
+        };
+
+        if let Ok((mut proxy_mesh, mut visibility)) = query_proxies.get_mut(proxy.entity) {
+            proxy_mesh.set_if_neq(mesh.clone());
+            visibility.set_if_neq(proxy_visibility(occluder.operation));
+        }
+    }
+}
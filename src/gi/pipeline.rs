@@ -6,25 +6,44 @@ use bevy::render::render_resource::*;
 use bevy::render::renderer::RenderDevice;
 use bevy::render::texture::GpuImage;
 
+use crate::gi::compositing::CameraTargets;
+use crate::gi::light_cookie::LightCookieAtlas;
 use crate::gi::pipeline_assets::{load_embedded_shader, LightPassPipelineAssets};
 use crate::gi::resource::ComputedTargetSizes;
 use crate::gi::types_gpu::{
+    GpuAreaLightSourceBuffer,
     GpuCameraParams,
+    GpuDirectionalLightBuffer,
+    GpuJfaParams,
+    GpuJfaStepParams,
     GpuLightOccluderBuffer,
     GpuLightPassParams,
     GpuLightSourceBuffer,
+    GpuOccluderTileBuffer,
+    GpuOccluderVertexBuffer,
+    GpuPolygonOccluderBuffer,
     GpuProbeDataBuffer,
     GpuSkylightMaskBuffer,
+    GpuSpotLightSourceBuffer,
+    GpuStaticSdfParams,
 };
 
 const SDF_TARGET_FORMAT: TextureFormat = TextureFormat::R16Float;
+const TRANSMITTANCE_TARGET_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+const MATERIAL_TARGET_FORMAT: TextureFormat = TextureFormat::Rgba32Uint;
 const SS_PROBE_TARGET_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
 const SS_BOUNCE_TARGET_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
 const SS_BLEND_TARGET_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
 const SS_FILTER_TARGET_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
 const SS_POSE_TARGET_FORMAT: TextureFormat = TextureFormat::Rg32Float;
+const JFA_TARGET_FORMAT: TextureFormat = TextureFormat::Rgba32Float;
 
 const SDF_PIPELINE_ENTRY: &str = "main";
+const STATIC_SDF_PIPELINE_ENTRY: &str = "main";
+const JFA_SEED_PIPELINE_ENTRY: &str = "main";
+const JFA_STEP_PIPELINE_ENTRY: &str = "main";
+const JFA_RESOLVE_PIPELINE_ENTRY: &str = "main";
+const LIGHT_BINNING_PIPELINE_ENTRY: &str = "main";
 const SS_PROBE_PIPELINE_ENTRY: &str = "main";
 const SS_BOUNCE_PIPELINE_ENTRY: &str = "main";
 const SS_BLEND_PIPELINE_ENTRY: &str = "main";
@@ -34,18 +53,27 @@ const SS_FILTER_PIPELINE_ENTRY: &str = "main";
 #[derive(Clone, Resource, ExtractResource, Default)]
 pub struct GiTargetsWrapper
 {
-    pub targets: Option<GiTargets>,
+    pub targets:    Option<GiTargets>,
+    /// Bumped whenever the targets are recreated, caches kept in them must
+    /// then be rebuilt.
+    pub generation: u32,
 }
 
 #[derive(Clone)]
 pub struct GiTargets
 {
-    pub sdf_target:       Handle<Image>,
-    pub ss_probe_target:  Handle<Image>,
-    pub ss_bounce_target: Handle<Image>,
-    pub ss_blend_target:  Handle<Image>,
-    pub ss_filter_target: Handle<Image>,
-    pub ss_pose_target:   Handle<Image>,
+    pub sdf_target:             Handle<Image>,
+    pub ss_probe_target:        Handle<Image>,
+    pub ss_bounce_target:       Handle<Image>,
+    pub ss_blend_target:        Handle<Image>,
+    pub ss_filter_target:       Handle<Image>,
+    pub ss_pose_target:         Handle<Image>,
+    pub jfa_ping_target:        Handle<Image>,
+    pub jfa_pong_target:        Handle<Image>,
+    pub static_sdf_target:      Handle<Image>,
+    pub transmittance_target:   Handle<Image>,
+    pub material_target:        Handle<Image>,
+    pub static_material_target: Handle<Image>,
 }
 
 impl GiTargets
@@ -83,12 +111,52 @@ impl GiTargets
             ImageFilterMode::Nearest,
         );
 
+        let jfa_ping_tex = create_texture_2d(
+            sizes.sdf_target_usize.into(),
+            JFA_TARGET_FORMAT,
+            ImageFilterMode::Nearest,
+        );
+        let jfa_pong_tex = create_texture_2d(
+            sizes.sdf_target_usize.into(),
+            JFA_TARGET_FORMAT,
+            ImageFilterMode::Nearest,
+        );
+
+        let static_sdf_tex = create_texture_2d(
+            sizes.static_sdf_target_usize.into(),
+            SDF_TARGET_FORMAT,
+            ImageFilterMode::Linear,
+        );
+
+        let transmittance_tex = create_texture_2d(
+            sizes.sdf_target_usize.into(),
+            TRANSMITTANCE_TARGET_FORMAT,
+            ImageFilterMode::Linear,
+        );
+
+        let material_tex = create_texture_2d(
+            sizes.sdf_target_usize.into(),
+            MATERIAL_TARGET_FORMAT,
+            ImageFilterMode::Nearest,
+        );
+        let static_material_tex = create_texture_2d(
+            sizes.static_sdf_target_usize.into(),
+            MATERIAL_TARGET_FORMAT,
+            ImageFilterMode::Nearest,
+        );
+
         let sdf_target: Handle<Image> = Handle::weak_from_u128(2390847209461232343);
         let ss_probe_target: Handle<Image> = Handle::weak_from_u128(3423231236817235162);
         let ss_bounce_target: Handle<Image> = Handle::weak_from_u128(3198273198312367527);
         let ss_blend_target: Handle<Image> = Handle::weak_from_u128(7782312739182735881);
         let ss_filter_target: Handle<Image> = Handle::weak_from_u128(8761232615172413412);
         let ss_pose_target: Handle<Image> = Handle::weak_from_u128(4728165084756128470);
+        let jfa_ping_target: Handle<Image> = Handle::weak_from_u128(5917302846150392716);
+        let jfa_pong_target: Handle<Image> = Handle::weak_from_u128(1208374650192847361);
+        let static_sdf_target: Handle<Image> = Handle::weak_from_u128(6634019273845120597);
+        let transmittance_target: Handle<Image> = Handle::weak_from_u128(2874610395716283049);
+        let material_target: Handle<Image> = Handle::weak_from_u128(9051736284019573626);
+        let static_material_target: Handle<Image> = Handle::weak_from_u128(4417093825610938174);
 
         images.insert(sdf_target.id(), sdf_tex);
         images.insert(ss_probe_target.id(), ss_probe_tex);
@@ -96,6 +164,12 @@ impl GiTargets
         images.insert(ss_blend_target.id(), ss_blend_tex);
         images.insert(ss_filter_target.id(), ss_filter_tex);
         images.insert(ss_pose_target.id(), ss_pose_tex);
+        images.insert(jfa_ping_target.id(), jfa_ping_tex);
+        images.insert(jfa_pong_target.id(), jfa_pong_tex);
+        images.insert(static_sdf_target.id(), static_sdf_tex);
+        images.insert(transmittance_target.id(), transmittance_tex);
+        images.insert(material_target.id(), material_tex);
+        images.insert(static_material_target.id(), static_material_tex);
 
         Self {
             sdf_target,
@@ -104,6 +178,12 @@ impl GiTargets
             ss_blend_target,
             ss_filter_target,
             ss_pose_target,
+            jfa_ping_target,
+            jfa_pong_target,
+            static_sdf_target,
+            transmittance_target,
+            material_target,
+            static_material_target,
         }
     }
 }
@@ -112,11 +192,16 @@ impl GiTargets
 #[derive(Resource)]
 pub struct LightPassPipelineBindGroups
 {
-    pub sdf_bind_group:       BindGroup,
-    pub ss_blend_bind_group:  BindGroup,
-    pub ss_probe_bind_group:  BindGroup,
-    pub ss_bounce_bind_group: BindGroup,
-    pub ss_filter_bind_group: BindGroup,
+    pub sdf_bind_group:           BindGroup,
+    pub static_sdf_bind_group:    BindGroup,
+    pub jfa_seed_bind_group:      BindGroup,
+    pub jfa_step_bind_groups:     Vec<BindGroup>,
+    pub jfa_resolve_bind_group:   BindGroup,
+    pub light_binning_bind_group: BindGroup,
+    pub ss_blend_bind_group:      BindGroup,
+    pub ss_probe_bind_group:      BindGroup,
+    pub ss_bounce_bind_group:     BindGroup,
+    pub ss_filter_bind_group:     BindGroup,
 }
 
 #[rustfmt::skip]
@@ -165,41 +250,84 @@ pub fn system_setup_gi_pipeline(
 #[derive(Resource)]
 pub struct LightPassPipeline
 {
-    pub sdf_bind_group_layout:       BindGroupLayout,
-    pub sdf_pipeline:                CachedComputePipelineId,
-    pub ss_probe_bind_group_layout:  BindGroupLayout,
-    pub ss_probe_pipeline:           CachedComputePipelineId,
-    pub ss_bounce_bind_group_layout: BindGroupLayout,
-    pub ss_bounce_pipeline:          CachedComputePipelineId,
-    pub ss_blend_bind_group_layout:  BindGroupLayout,
-    pub ss_blend_pipeline:           CachedComputePipelineId,
-    pub ss_filter_bind_group_layout: BindGroupLayout,
-    pub ss_filter_pipeline:          CachedComputePipelineId,
+    pub sdf_bind_group_layout:           BindGroupLayout,
+    pub sdf_pipeline:                    CachedComputePipelineId,
+    pub static_sdf_bind_group_layout:    BindGroupLayout,
+    pub static_sdf_pipeline:             CachedComputePipelineId,
+    pub jfa_seed_bind_group_layout:      BindGroupLayout,
+    pub jfa_seed_pipeline:               CachedComputePipelineId,
+    pub jfa_step_bind_group_layout:      BindGroupLayout,
+    pub jfa_step_pipeline:               CachedComputePipelineId,
+    pub jfa_resolve_bind_group_layout:   BindGroupLayout,
+    pub jfa_resolve_pipeline:            CachedComputePipelineId,
+    pub light_binning_bind_group_layout: BindGroupLayout,
+    pub light_binning_pipeline:          CachedComputePipelineId,
+    pub ss_probe_bind_group_layout:      BindGroupLayout,
+    pub ss_probe_pipeline:               CachedComputePipelineId,
+    pub ss_bounce_bind_group_layout:     BindGroupLayout,
+    pub ss_bounce_pipeline:              CachedComputePipelineId,
+    pub ss_blend_bind_group_layout:      BindGroupLayout,
+    pub ss_blend_pipeline:               CachedComputePipelineId,
+    pub ss_filter_bind_group_layout:     BindGroupLayout,
+    pub ss_filter_pipeline:              CachedComputePipelineId,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn system_queue_bind_groups(
     mut commands: Commands,
     pipeline: Res<LightPassPipeline>,
     gpu_images: Res<RenderAssets<GpuImage>>,
     targets_wrapper: Res<GiTargetsWrapper>,
+    camera_targets: Res<CameraTargets>,
+    light_cookies: Res<LightCookieAtlas>,
     gi_compute_assets: Res<LightPassPipelineAssets>,
     render_device: Res<RenderDevice>,
 )
 {
     if let (
         Some(light_sources),
+        Some(spot_lights),
+        Some(area_lights),
+        Some(directional_lights),
         Some(light_occluders),
+        Some(polygon_occluders),
+        Some(occluder_vertices),
+        Some(occluder_tiles),
         Some(camera_params),
         Some(gi_state),
         Some(probes),
         Some(skylight_masks),
+        Some(skylight_vertices),
+        Some(light_tiles),
+        Some(jfa_params),
+        Some(static_occluders),
+        Some(static_polygon_occluders),
+        Some(static_occluder_vertices),
+        Some(static_occluder_tiles),
+        Some(static_camera_params),
+        Some(static_sdf_params),
     ) = (
         gi_compute_assets.light_sources.binding(),
+        gi_compute_assets.spot_lights.binding(),
+        gi_compute_assets.area_lights.binding(),
+        gi_compute_assets.directional_lights.binding(),
         gi_compute_assets.light_occluders.binding(),
+        gi_compute_assets.polygon_occluders.binding(),
+        gi_compute_assets.occluder_vertices.binding(),
+        gi_compute_assets.occluder_tiles.binding(),
         gi_compute_assets.camera_params.binding(),
         gi_compute_assets.light_pass_params.binding(),
         gi_compute_assets.probes.binding(),
         gi_compute_assets.skylight_masks.binding(),
+        gi_compute_assets.skylight_vertices.binding(),
+        gi_compute_assets.light_tiles.as_ref(),
+        gi_compute_assets.jfa_params.binding(),
+        gi_compute_assets.static_occluders.binding(),
+        gi_compute_assets.static_polygon_occluders.binding(),
+        gi_compute_assets.static_occluder_vertices.binding(),
+        gi_compute_assets.static_occluder_tiles.binding(),
+        gi_compute_assets.static_camera_params.binding(),
+        gi_compute_assets.static_sdf_params.binding(),
     ) {
         let targets = targets_wrapper
             .targets
@@ -224,6 +352,33 @@ pub fn system_queue_bind_groups(
         let ss_pose_image = gpu_images
             .get(&targets.ss_pose_target)
             .expect("SS Pose target not found");
+        let jfa_ping_image = gpu_images
+            .get(&targets.jfa_ping_target)
+            .expect("JFA ping target not found");
+        let jfa_pong_image = gpu_images
+            .get(&targets.jfa_pong_target)
+            .expect("JFA pong target not found");
+        let static_sdf_image = gpu_images
+            .get(&targets.static_sdf_target)
+            .expect("Static SDF target not found");
+        let transmittance_image = gpu_images
+            .get(&targets.transmittance_target)
+            .expect("Transmittance target not found");
+        let material_image = gpu_images
+            .get(&targets.material_target)
+            .expect("Material target not found");
+        let static_material_image = gpu_images
+            .get(&targets.static_material_target)
+            .expect("Static material target not found");
+        let emissive_image = gpu_images
+            .get(&camera_targets.emissive_target)
+            .expect("Emissive target not found");
+        let occluder_image = gpu_images
+            .get(&camera_targets.occluder_target)
+            .expect("Occluder target not found");
+        let light_cookies_image = gpu_images
+            .get(&light_cookies.image)
+            .expect("Light cookies atlas not found");
 
         let sdf_bind_group = render_device.create_bind_group(
             "gi_sdf_bind_group",
@@ -241,6 +396,190 @@ pub fn system_queue_bind_groups(
                     binding:  2,
                     resource: BindingResource::TextureView(&sdf_view_image.texture_view),
                 },
+                BindGroupEntry {
+                    binding:  3,
+                    resource: polygon_occluders.clone(),
+                },
+                BindGroupEntry {
+                    binding:  4,
+                    resource: occluder_vertices.clone(),
+                },
+                BindGroupEntry {
+                    binding:  5,
+                    resource: occluder_tiles.clone(),
+                },
+                BindGroupEntry {
+                    binding:  6,
+                    resource: BindingResource::TextureView(&static_sdf_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  7,
+                    resource: BindingResource::Sampler(&static_sdf_image.sampler),
+                },
+                BindGroupEntry {
+                    binding:  8,
+                    resource: static_sdf_params.clone(),
+                },
+                BindGroupEntry {
+                    binding:  9,
+                    resource: BindingResource::TextureView(&transmittance_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  10,
+                    resource: BindingResource::TextureView(&material_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  11,
+                    resource: BindingResource::TextureView(&static_material_image.texture_view),
+                },
+            ],
+        );
+
+        let static_sdf_bind_group = render_device.create_bind_group(
+            "gi_static_sdf_bind_group",
+            &pipeline.static_sdf_bind_group_layout,
+            &[
+                BindGroupEntry {
+                    binding:  0,
+                    resource: static_camera_params.clone(),
+                },
+                BindGroupEntry {
+                    binding:  1,
+                    resource: static_occluders.clone(),
+                },
+                BindGroupEntry {
+                    binding:  2,
+                    resource: BindingResource::TextureView(&static_sdf_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  3,
+                    resource: static_polygon_occluders.clone(),
+                },
+                BindGroupEntry {
+                    binding:  4,
+                    resource: static_occluder_vertices.clone(),
+                },
+                BindGroupEntry {
+                    binding:  5,
+                    resource: static_occluder_tiles.clone(),
+                },
+                BindGroupEntry {
+                    binding:  10,
+                    resource: BindingResource::TextureView(&static_material_image.texture_view),
+                },
+            ],
+        );
+
+        let jfa_seed_bind_group = render_device.create_bind_group(
+            "gi_jfa_seed_bind_group",
+            &pipeline.jfa_seed_bind_group_layout,
+            &[
+                BindGroupEntry {
+                    binding:  0,
+                    resource: camera_params.clone(),
+                },
+                BindGroupEntry {
+                    binding:  1,
+                    resource: jfa_params.clone(),
+                },
+                BindGroupEntry {
+                    binding:  2,
+                    resource: BindingResource::TextureView(&occluder_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  3,
+                    resource: BindingResource::TextureView(&jfa_ping_image.texture_view),
+                },
+            ],
+        );
+
+        // Steps ping-pong between the targets, starting from the seeds.
+        let mut jfa_targets = [jfa_ping_image, jfa_pong_image];
+        let mut jfa_step_bind_groups = Vec::with_capacity(gi_compute_assets.jfa_steps.len());
+        for step_params in gi_compute_assets.jfa_steps.iter() {
+            let Some(step_params) = step_params.binding() else {
+                continue;
+            };
+            jfa_step_bind_groups.push(render_device.create_bind_group(
+                "gi_jfa_step_bind_group",
+                &pipeline.jfa_step_bind_group_layout,
+                &[
+                    BindGroupEntry {
+                        binding:  0,
+                        resource: step_params,
+                    },
+                    BindGroupEntry {
+                        binding:  1,
+                        resource: BindingResource::TextureView(&jfa_targets[0].texture_view),
+                    },
+                    BindGroupEntry {
+                        binding:  2,
+                        resource: BindingResource::TextureView(&jfa_targets[1].texture_view),
+                    },
+                ],
+            ));
+            jfa_targets.swap(0, 1);
+        }
+
+        let jfa_resolve_bind_group = render_device.create_bind_group(
+            "gi_jfa_resolve_bind_group",
+            &pipeline.jfa_resolve_bind_group_layout,
+            &[
+                BindGroupEntry {
+                    binding:  0,
+                    resource: camera_params.clone(),
+                },
+                BindGroupEntry {
+                    binding:  1,
+                    resource: BindingResource::TextureView(&jfa_targets[0].texture_view),
+                },
+                BindGroupEntry {
+                    binding:  2,
+                    resource: BindingResource::TextureView(&sdf_view_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  3,
+                    resource: jfa_params.clone(),
+                },
+                BindGroupEntry {
+                    binding:  4,
+                    resource: BindingResource::TextureView(&transmittance_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  5,
+                    resource: BindingResource::TextureView(&material_image.texture_view),
+                },
+            ],
+        );
+
+        let light_binning_bind_group = render_device.create_bind_group(
+            "gi_light_binning_bind_group",
+            &pipeline.light_binning_bind_group_layout,
+            &[
+                BindGroupEntry {
+                    binding:  0,
+                    resource: camera_params.clone(),
+                },
+                BindGroupEntry {
+                    binding:  1,
+                    resource: gi_state.clone(),
+                },
+                BindGroupEntry {
+                    binding:  2,
+                    resource: light_sources.clone(),
+                },
+                BindGroupEntry {
+                    binding:  3,
+                    resource: spot_lights.clone(),
+                },
+                BindGroupEntry {
+                    binding:  4,
+                    resource: area_lights.clone(),
+                },
+                BindGroupEntry {
+                    binding:  5,
+                    resource: light_tiles.as_entire_binding(),
+                },
             ],
         );
 
@@ -280,6 +619,46 @@ pub fn system_queue_bind_groups(
                     binding:  7,
                     resource: BindingResource::TextureView(&ss_probe_image.texture_view),
                 },
+                BindGroupEntry {
+                    binding:  8,
+                    resource: spot_lights.clone(),
+                },
+                BindGroupEntry {
+                    binding:  9,
+                    resource: area_lights.clone(),
+                },
+                BindGroupEntry {
+                    binding:  10,
+                    resource: directional_lights.clone(),
+                },
+                BindGroupEntry {
+                    binding:  11,
+                    resource: BindingResource::TextureView(&emissive_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  12,
+                    resource: BindingResource::Sampler(&emissive_image.sampler),
+                },
+                BindGroupEntry {
+                    binding:  13,
+                    resource: BindingResource::TextureView(&light_cookies_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  14,
+                    resource: BindingResource::Sampler(&light_cookies_image.sampler),
+                },
+                BindGroupEntry {
+                    binding:  15,
+                    resource: light_tiles.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding:  16,
+                    resource: BindingResource::TextureView(&transmittance_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  17,
+                    resource: skylight_vertices.clone(),
+                },
             ],
         );
 
@@ -311,6 +690,14 @@ pub fn system_queue_bind_groups(
                     binding:  5,
                     resource: BindingResource::TextureView(&ss_bounce_image.texture_view),
                 },
+                BindGroupEntry {
+                    binding:  6,
+                    resource: BindingResource::TextureView(&transmittance_image.texture_view),
+                },
+                BindGroupEntry {
+                    binding:  7,
+                    resource: BindingResource::TextureView(&material_image.texture_view),
+                },
             ],
         );
 
@@ -385,11 +772,20 @@ pub fn system_queue_bind_groups(
                     binding:  7,
                     resource: BindingResource::TextureView(&ss_pose_image.texture_view),
                 },
+                BindGroupEntry {
+                    binding:  8,
+                    resource: BindingResource::TextureView(&transmittance_image.texture_view),
+                },
             ],
         );
 
         commands.insert_resource(LightPassPipelineBindGroups {
             sdf_bind_group,
+            static_sdf_bind_group,
+            jfa_seed_bind_group,
+            jfa_step_bind_groups,
+            jfa_resolve_bind_group,
+            light_binning_bind_group,
             ss_probe_bind_group,
             ss_bounce_bind_group,
             ss_blend_bind_group,
@@ -404,8 +800,153 @@ impl FromWorld for LightPassPipeline
     {
         let render_device = world.resource::<RenderDevice>();
 
+        // Bindings shared by the SDF pass and the static SDF cache pass.
+        let sdf_occluder_entries = [
+            // Camera.
+            BindGroupLayoutEntry {
+                binding:    0,
+                visibility: ShaderStages::COMPUTE,
+                ty:         BindingType::Buffer {
+                    ty:                 BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size:   Some(GpuCameraParams::min_size()),
+                },
+                count:      None,
+            },
+            // Light occluders.
+            BindGroupLayoutEntry {
+                binding:    1,
+                visibility: ShaderStages::COMPUTE,
+                ty:         BindingType::Buffer {
+                    ty:                 BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size:   Some(GpuLightOccluderBuffer::min_size()),
+                },
+                count:      None,
+            },
+            // SDF texture.
+            BindGroupLayoutEntry {
+                binding:    2,
+                visibility: ShaderStages::COMPUTE,
+                ty:         BindingType::StorageTexture {
+                    access:         StorageTextureAccess::ReadWrite,
+                    format:         SDF_TARGET_FORMAT,
+                    view_dimension: TextureViewDimension::D2,
+                },
+                count:      None,
+            },
+            // Polygon occluders.
+            BindGroupLayoutEntry {
+                binding:    3,
+                visibility: ShaderStages::COMPUTE,
+                ty:         BindingType::Buffer {
+                    ty:                 BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size:   Some(GpuPolygonOccluderBuffer::min_size()),
+                },
+                count:      None,
+            },
+            // Polygon occluder vertices.
+            BindGroupLayoutEntry {
+                binding:    4,
+                visibility: ShaderStages::COMPUTE,
+                ty:         BindingType::Buffer {
+                    ty:                 BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size:   Some(GpuOccluderVertexBuffer::min_size()),
+                },
+                count:      None,
+            },
+            // Occluder tiles.
+            BindGroupLayoutEntry {
+                binding:    5,
+                visibility: ShaderStages::COMPUTE,
+                ty:         BindingType::Buffer {
+                    ty:                 BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size:   Some(GpuOccluderTileBuffer::min_size()),
+                },
+                count:      None,
+            },
+            // Material.
+            BindGroupLayoutEntry {
+                binding:    10,
+                visibility: ShaderStages::COMPUTE,
+                ty:         BindingType::StorageTexture {
+                    access:         StorageTextureAccess::WriteOnly,
+                    format:         MATERIAL_TARGET_FORMAT,
+                    view_dimension: TextureViewDimension::D2,
+                },
+                count:      None,
+            },
+        ];
+
+        let static_sdf_bind_group_layout = render_device
+            .create_bind_group_layout("static_sdf_bind_group_layout", &sdf_occluder_entries);
+
         let sdf_bind_group_layout = render_device.create_bind_group_layout(
             "sdf_bind_group_layout",
+            &[
+                sdf_occluder_entries.as_slice(),
+                &[
+                    // Static SDF.
+                    BindGroupLayoutEntry {
+                        binding:    6,
+                        visibility: ShaderStages::COMPUTE,
+                        ty:         BindingType::Texture {
+                            sample_type:    TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled:   false,
+                        },
+                        count:      None,
+                    },
+                    // Static SDF sampler.
+                    BindGroupLayoutEntry {
+                        binding:    7,
+                        visibility: ShaderStages::COMPUTE,
+                        ty:         BindingType::Sampler(SamplerBindingType::Filtering),
+                        count:      None,
+                    },
+                    // Static SDF params.
+                    BindGroupLayoutEntry {
+                        binding:    8,
+                        visibility: ShaderStages::COMPUTE,
+                        ty:         BindingType::Buffer {
+                            ty:                 BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size:   Some(GpuStaticSdfParams::min_size()),
+                        },
+                        count:      None,
+                    },
+                    // Transmittance.
+                    BindGroupLayoutEntry {
+                        binding:    9,
+                        visibility: ShaderStages::COMPUTE,
+                        ty:         BindingType::StorageTexture {
+                            access:         StorageTextureAccess::WriteOnly,
+                            format:         TRANSMITTANCE_TARGET_FORMAT,
+                            view_dimension: TextureViewDimension::D2,
+                        },
+                        count:      None,
+                    },
+                    // Static material.
+                    BindGroupLayoutEntry {
+                        binding:    11,
+                        visibility: ShaderStages::COMPUTE,
+                        ty:         BindingType::Texture {
+                            sample_type:    TextureSampleType::Uint,
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled:   false,
+                        },
+                        count:      None,
+                    },
+                ],
+            ]
+            .concat(),
+        );
+
+        let jfa_seed_bind_group_layout = render_device.create_bind_group_layout(
+            "jfa_seed_bind_group_layout",
             &[
                 // Camera.
                 BindGroupLayoutEntry {
@@ -418,14 +959,103 @@ impl FromWorld for LightPassPipeline
                     },
                     count:      None,
                 },
-                // Light occluders.
+                // JFA params.
                 BindGroupLayoutEntry {
                     binding:    1,
                     visibility: ShaderStages::COMPUTE,
                     ty:         BindingType::Buffer {
-                        ty:                 BufferBindingType::Storage { read_only: true },
+                        ty:                 BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuJfaParams::min_size()),
+                    },
+                    count:      None,
+                },
+                // Occluder mask.
+                BindGroupLayoutEntry {
+                    binding:    2,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Texture {
+                        sample_type:    TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled:   false,
+                    },
+                    count:      None,
+                },
+                // JFA out.
+                BindGroupLayoutEntry {
+                    binding:    3,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::StorageTexture {
+                        access:         StorageTextureAccess::WriteOnly,
+                        format:         JFA_TARGET_FORMAT,
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count:      None,
+                },
+            ],
+        );
+
+        let jfa_step_bind_group_layout = render_device.create_bind_group_layout(
+            "jfa_step_bind_group_layout",
+            &[
+                // Step params.
+                BindGroupLayoutEntry {
+                    binding:    0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Uniform,
                         has_dynamic_offset: false,
-                        min_binding_size:   Some(GpuLightOccluderBuffer::min_size()),
+                        min_binding_size:   Some(GpuJfaStepParams::min_size()),
+                    },
+                    count:      None,
+                },
+                // JFA in.
+                BindGroupLayoutEntry {
+                    binding:    1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::StorageTexture {
+                        access:         StorageTextureAccess::ReadOnly,
+                        format:         JFA_TARGET_FORMAT,
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count:      None,
+                },
+                // JFA out.
+                BindGroupLayoutEntry {
+                    binding:    2,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::StorageTexture {
+                        access:         StorageTextureAccess::WriteOnly,
+                        format:         JFA_TARGET_FORMAT,
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count:      None,
+                },
+            ],
+        );
+
+        let jfa_resolve_bind_group_layout = render_device.create_bind_group_layout(
+            "jfa_resolve_bind_group_layout",
+            &[
+                // Camera.
+                BindGroupLayoutEntry {
+                    binding:    0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuCameraParams::min_size()),
+                    },
+                    count:      None,
+                },
+                // JFA in.
+                BindGroupLayoutEntry {
+                    binding:    1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::StorageTexture {
+                        access:         StorageTextureAccess::ReadOnly,
+                        format:         JFA_TARGET_FORMAT,
+                        view_dimension: TextureViewDimension::D2,
                     },
                     count:      None,
                 },
@@ -440,6 +1070,111 @@ impl FromWorld for LightPassPipeline
                     },
                     count:      None,
                 },
+                // JFA params.
+                BindGroupLayoutEntry {
+                    binding:    3,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuJfaParams::min_size()),
+                    },
+                    count:      None,
+                },
+                // Transmittance.
+                BindGroupLayoutEntry {
+                    binding:    4,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::StorageTexture {
+                        access:         StorageTextureAccess::ReadWrite,
+                        format:         TRANSMITTANCE_TARGET_FORMAT,
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count:      None,
+                },
+                // Material.
+                BindGroupLayoutEntry {
+                    binding:    5,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::StorageTexture {
+                        access:         StorageTextureAccess::WriteOnly,
+                        format:         MATERIAL_TARGET_FORMAT,
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count:      None,
+                },
+            ],
+        );
+
+        let light_binning_bind_group_layout = render_device.create_bind_group_layout(
+            "light_binning_bind_group_layout",
+            &[
+                // Camera.
+                BindGroupLayoutEntry {
+                    binding:    0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuCameraParams::min_size()),
+                    },
+                    count:      None,
+                },
+                // GI State.
+                BindGroupLayoutEntry {
+                    binding:    1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuLightPassParams::min_size()),
+                    },
+                    count:      None,
+                },
+                // Light sources.
+                BindGroupLayoutEntry {
+                    binding:    2,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuLightSourceBuffer::min_size()),
+                    },
+                    count:      None,
+                },
+                // Spot light sources.
+                BindGroupLayoutEntry {
+                    binding:    3,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuSpotLightSourceBuffer::min_size()),
+                    },
+                    count:      None,
+                },
+                // Line and area light sources.
+                BindGroupLayoutEntry {
+                    binding:    4,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuAreaLightSourceBuffer::min_size()),
+                    },
+                    count:      None,
+                },
+                // Light tiles.
+                BindGroupLayoutEntry {
+                    binding:    5,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size:   None,
+                    },
+                    count:      None,
+                },
             ],
         );
 
@@ -530,6 +1265,108 @@ impl FromWorld for LightPassPipeline
                     },
                     count:      None,
                 },
+                // Spot light sources.
+                BindGroupLayoutEntry {
+                    binding:    8,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuSpotLightSourceBuffer::min_size()),
+                    },
+                    count:      None,
+                },
+                // Line and area light sources.
+                BindGroupLayoutEntry {
+                    binding:    9,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuAreaLightSourceBuffer::min_size()),
+                    },
+                    count:      None,
+                },
+                // Directional lights.
+                BindGroupLayoutEntry {
+                    binding:    10,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuDirectionalLightBuffer::min_size()),
+                    },
+                    count:      None,
+                },
+                // Emissive.
+                BindGroupLayoutEntry {
+                    binding:    11,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Texture {
+                        sample_type:    TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled:   false,
+                    },
+                    count:      None,
+                },
+                // Emissive Sampler.
+                BindGroupLayoutEntry {
+                    binding:    12,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Sampler(SamplerBindingType::Filtering),
+                    count:      None,
+                },
+                // Light cookies.
+                BindGroupLayoutEntry {
+                    binding:    13,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Texture {
+                        sample_type:    TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2Array,
+                        multisampled:   false,
+                    },
+                    count:      None,
+                },
+                // Light cookies Sampler.
+                BindGroupLayoutEntry {
+                    binding:    14,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Sampler(SamplerBindingType::Filtering),
+                    count:      None,
+                },
+                // Light tiles.
+                BindGroupLayoutEntry {
+                    binding:    15,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size:   None,
+                    },
+                    count:      None,
+                },
+                // Transmittance.
+                BindGroupLayoutEntry {
+                    binding:    16,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Texture {
+                        sample_type:    TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled:   false,
+                    },
+                    count:      None,
+                },
+                // Skylight mask vertices.
+                BindGroupLayoutEntry {
+                    binding:    17,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Buffer {
+                        ty:                 BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size:   Some(GpuOccluderVertexBuffer::min_size()),
+                    },
+                    count:      None,
+                },
             ],
         );
 
@@ -598,6 +1435,28 @@ impl FromWorld for LightPassPipeline
                     },
                     count:      None,
                 },
+                // Transmittance.
+                BindGroupLayoutEntry {
+                    binding:    6,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Texture {
+                        sample_type:    TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled:   false,
+                    },
+                    count:      None,
+                },
+                // Material.
+                BindGroupLayoutEntry {
+                    binding:    7,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Texture {
+                        sample_type:    TextureSampleType::Uint,
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled:   false,
+                    },
+                    count:      None,
+                },
             ],
         );
 
@@ -767,13 +1626,38 @@ impl FromWorld for LightPassPipeline
                     },
                     count:      None,
                 },
+                // Transmittance.
+                BindGroupLayoutEntry {
+                    binding:    8,
+                    visibility: ShaderStages::COMPUTE,
+                    ty:         BindingType::Texture {
+                        sample_type:    TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled:   false,
+                    },
+                    count:      None,
+                },
             ],
         );
 
-        let (shader_sdf, gi_ss_probe, gi_ss_bounce, gi_ss_blend, gi_ss_filter) = {
+        let (
+            shader_sdf,
+            gi_jfa_seed,
+            gi_jfa_step,
+            gi_jfa_resolve,
+            gi_light_binning,
+            gi_ss_probe,
+            gi_ss_bounce,
+            gi_ss_blend,
+            gi_ss_filter,
+        ) = {
             let assets_server = world.resource::<AssetServer>();
             (
                 load_embedded_shader(assets_server, "gi_sdf.wgsl"),
+                load_embedded_shader(assets_server, "gi_jfa_seed.wgsl"),
+                load_embedded_shader(assets_server, "gi_jfa_step.wgsl"),
+                load_embedded_shader(assets_server, "gi_jfa_resolve.wgsl"),
+                load_embedded_shader(assets_server, "gi_light_binning.wgsl"),
                 load_embedded_shader(assets_server, "gi_ss_probe.wgsl"),
                 load_embedded_shader(assets_server, "gi_ss_bounce.wgsl"),
                 load_embedded_shader(assets_server, "gi_ss_blend.wgsl"),
@@ -786,13 +1670,66 @@ impl FromWorld for LightPassPipeline
         let sdf_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
             label:                            Some("gi_sdf_pipeline".into()),
             layout:                           vec![sdf_bind_group_layout.clone()],
-            shader:                           shader_sdf,
+            shader:                           shader_sdf.clone(),
             shader_defs:                      vec![],
             entry_point:                      SDF_PIPELINE_ENTRY.into(),
             push_constant_ranges:             vec![],
             zero_initialize_workgroup_memory: false,
         });
 
+        let static_sdf_pipeline =
+            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label:                            Some("gi_static_sdf_pipeline".into()),
+                layout:                           vec![static_sdf_bind_group_layout.clone()],
+                shader:                           shader_sdf,
+                shader_defs:                      vec!["STATIC_SDF".into()],
+                entry_point:                      STATIC_SDF_PIPELINE_ENTRY.into(),
+                push_constant_ranges:             vec![],
+                zero_initialize_workgroup_memory: false,
+            });
+
+        let jfa_seed_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label:                            Some("gi_jfa_seed_pipeline".into()),
+            layout:                           vec![jfa_seed_bind_group_layout.clone()],
+            shader:                           gi_jfa_seed,
+            shader_defs:                      vec![],
+            entry_point:                      JFA_SEED_PIPELINE_ENTRY.into(),
+            push_constant_ranges:             vec![],
+            zero_initialize_workgroup_memory: false,
+        });
+
+        let jfa_step_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label:                            Some("gi_jfa_step_pipeline".into()),
+            layout:                           vec![jfa_step_bind_group_layout.clone()],
+            shader:                           gi_jfa_step,
+            shader_defs:                      vec![],
+            entry_point:                      JFA_STEP_PIPELINE_ENTRY.into(),
+            push_constant_ranges:             vec![],
+            zero_initialize_workgroup_memory: false,
+        });
+
+        let jfa_resolve_pipeline =
+            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label:                            Some("gi_jfa_resolve_pipeline".into()),
+                layout:                           vec![jfa_resolve_bind_group_layout.clone()],
+                shader:                           gi_jfa_resolve,
+                shader_defs:                      vec![],
+                entry_point:                      JFA_RESOLVE_PIPELINE_ENTRY.into(),
+                push_constant_ranges:             vec![],
+                zero_initialize_workgroup_memory: false,
+            });
+
+        let light_binning_pipeline =
+            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label:                            Some("gi_light_binning_pipeline".into()),
+                layout:                           vec![light_binning_bind_group_layout.clone()],
+                shader:                           gi_light_binning,
+                shader_defs:                      vec![],
+                entry_point:                      LIGHT_BINNING_PIPELINE_ENTRY.into(),
+                push_constant_ranges:             vec![],
+                zero_initialize_workgroup_memory: false,
+            });
+
         let ss_probe_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
             label:                            Some("gi_ss_probe_pipeline".into()),
             layout:                           vec![ss_probe_bind_group_layout.clone()],
@@ -838,6 +1775,21 @@ impl FromWorld for LightPassPipeline
             sdf_bind_group_layout,
             sdf_pipeline,
             //
+            static_sdf_bind_group_layout,
+            static_sdf_pipeline,
+            //
+            jfa_seed_bind_group_layout,
+            jfa_seed_pipeline,
+            //
+            jfa_step_bind_group_layout,
+            jfa_step_pipeline,
+            //
+            jfa_resolve_bind_group_layout,
+            jfa_resolve_pipeline,
+            //
+            light_binning_bind_group_layout,
+            light_binning_pipeline,
+            //
             ss_probe_bind_group_layout,
             ss_probe_pipeline,
             //
@@ -1,27 +1,74 @@
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use bevy::asset::io::AssetSourceId;
 use bevy::asset::AssetPath;
 use bevy::prelude::*;
-use bevy::render::render_resource::{StorageBuffer, UniformBuffer};
+use bevy::render::render_resource::{
+    Buffer,
+    BufferDescriptor,
+    BufferUsages,
+    StorageBuffer,
+    UniformBuffer,
+};
 use bevy::render::renderer::{RenderDevice, RenderQueue};
 use bevy::render::Extract;
-use rand::Rng;
 
-use crate::gi::constants::GI_SCREEN_PROBE_SIZE;
-use crate::gi::resource::ComputedTargetSizes;
-use crate::gi::types::{LightOccluder2D, OmniLightSource2D, SkylightLight2D, SkylightMask2D};
+use crate::gi::constants::{
+    GI_SCREEN_PROBE_SIZE,
+    LIGHT_TILE_STRIDE,
+    OCCLUDER_TILE_SIZE,
+    SDF_VIEW_SCALE,
+    STATIC_SDF_SCALE,
+};
+use crate::gi::light_animation::{self, LightAnimation2D};
+use crate::gi::light_cookie::LightCookieAtlas;
+use crate::gi::mesh_occluder::MeshOccluderOutlines;
+use crate::gi::pipeline::GiTargetsWrapper;
+use crate::gi::resource::{ComputedTargetSizes, SdfMode};
+use crate::gi::sprite_occluder::SpriteOccluderMaterial;
+use crate::gi::tile_occluder::TileOccluderMaterial;
+use crate::gi::types::{
+    AreaLight2D,
+    DirectionalLight2D,
+    LightCookie2D,
+    LightOccluder2D,
+    LineLight2D,
+    MeshOccluder2D,
+    OmniLightSource2D,
+    PolygonOccluder2D,
+    SkylightLight2D,
+    SkylightMask2D,
+    SpotLight2D,
+    StaticOccluder2D,
+    TranslucentOccluder2D,
+};
 use crate::gi::types_gpu::{
+    GpuAreaLightSource,
+    GpuAreaLightSourceBuffer,
     GpuCameraParams,
+    GpuDirectionalLight,
+    GpuDirectionalLightBuffer,
+    GpuJfaParams,
+    GpuJfaStepParams,
+    GpuLightCookie,
     GpuLightOccluder2D,
     GpuLightOccluderBuffer,
     GpuLightPassParams,
     GpuLightSourceBuffer,
+    GpuOccluderTileBuffer,
+    GpuOccluderVertexBuffer,
     GpuOmniLightSource,
+    GpuPolygonOccluder,
+    GpuPolygonOccluderBuffer,
     GpuProbeDataBuffer,
     GpuSkylightMaskBuffer,
     GpuSkylightMaskData,
+    GpuSpotLightSource,
+    GpuSpotLightSourceBuffer,
+    GpuStaticSdfParams,
 };
+use crate::gi::WORKGROUP_SIZE;
 use crate::prelude::BevyMagicLight2DSettings;
 use crate::FloorCamera;
 
@@ -40,6 +87,7 @@ pub(crate) fn system_load_embedded_shader_dependencies(
     embedded_shader_deps.loaded_shaders.push(load_embedded_shader(&asset_server, "gi_camera.wgsl"));
     embedded_shader_deps.loaded_shaders.push(load_embedded_shader(&asset_server, "gi_halton.wgsl"));
     embedded_shader_deps.loaded_shaders.push(load_embedded_shader(&asset_server, "gi_math.wgsl"));
+    embedded_shader_deps.loaded_shaders.push(load_embedded_shader(&asset_server, "gi_occluder_shapes.wgsl"));
     embedded_shader_deps.loaded_shaders.push(load_embedded_shader(&asset_server, "gi_post_processing.wgsl"));
     embedded_shader_deps.loaded_shaders.push(load_embedded_shader(&asset_server, "gi_raymarch.wgsl"));
     embedded_shader_deps.loaded_shaders.push(load_embedded_shader(&asset_server, "gi_types.wgsl"));
@@ -53,15 +101,60 @@ pub(crate) fn load_embedded_shader(asset_server: &AssetServer, shader_file: &str
     asset_server.load(AssetPath::from_path(&path.join(shader_file)).with_source(&source))
 }
 
+/// State of the static occluder SDF cache.
+#[rustfmt::skip]
+#[derive(Default)]
+pub struct StaticSdfCache {
+    /// World region covered by the cache.
+    pub region:         Rect,
+    pub sdf_size:       UVec2,
+    /// Generation of the targets holding the cache, recreated targets are
+    /// blank.
+    pub generation:     u32,
+    /// Number of static occluders, including hidden ones, to detect removals.
+    pub occluder_count: usize,
+    /// Set when the cache must be rebuilt, cleared once the light pass node
+    /// has dispatched the rebuild.
+    pub dirty:          AtomicBool,
+}
+
 #[rustfmt::skip]
 #[derive(Default, Resource)]
 pub struct LightPassPipelineAssets {
-    pub camera_params:     UniformBuffer<GpuCameraParams>,
-    pub light_pass_params: UniformBuffer<GpuLightPassParams>,
-    pub light_sources:     StorageBuffer<GpuLightSourceBuffer>,
-    pub light_occluders:   StorageBuffer<GpuLightOccluderBuffer>,
-    pub probes:            StorageBuffer<GpuProbeDataBuffer>,
-    pub skylight_masks:    StorageBuffer<GpuSkylightMaskBuffer>,
+    pub camera_params:      UniformBuffer<GpuCameraParams>,
+    pub light_pass_params:  UniformBuffer<GpuLightPassParams>,
+    pub light_sources:      StorageBuffer<GpuLightSourceBuffer>,
+    pub spot_lights:        StorageBuffer<GpuSpotLightSourceBuffer>,
+    pub area_lights:        StorageBuffer<GpuAreaLightSourceBuffer>,
+    pub directional_lights: StorageBuffer<GpuDirectionalLightBuffer>,
+    pub light_occluders:    StorageBuffer<GpuLightOccluderBuffer>,
+    pub polygon_occluders:  StorageBuffer<GpuPolygonOccluderBuffer>,
+    pub occluder_vertices:  StorageBuffer<GpuOccluderVertexBuffer>,
+    /// Light occluders binned per SDF tile.
+    pub occluder_tiles:     StorageBuffer<GpuOccluderTileBuffer>,
+    pub probes:             StorageBuffer<GpuProbeDataBuffer>,
+    pub skylight_masks:     StorageBuffer<GpuSkylightMaskBuffer>,
+    /// Polygon vertices of the skylight masks.
+    pub skylight_vertices:  StorageBuffer<GpuOccluderVertexBuffer>,
+    /// Per-tile light lists, written by the light binning pass.
+    pub light_tiles:        Option<Buffer>,
+    pub jfa_params:         UniformBuffer<GpuJfaParams>,
+    /// Jump flood passes, the step length halves with each pass.
+    pub jfa_steps:          Vec<UniformBuffer<GpuJfaStepParams>>,
+    pub sdf_mode:           SdfMode,
+    /// Whether the SDF is built by jump flooding or any sprite occluder is
+    /// visible, the jump flood is skipped otherwise.
+    pub jfa_enabled:        bool,
+
+    /// Static occluders baked into the static SDF cache.
+    pub static_occluders:         StorageBuffer<GpuLightOccluderBuffer>,
+    pub static_polygon_occluders: StorageBuffer<GpuPolygonOccluderBuffer>,
+    pub static_occluder_vertices: StorageBuffer<GpuOccluderVertexBuffer>,
+    pub static_occluder_tiles:    StorageBuffer<GpuOccluderTileBuffer>,
+    /// Maps the static SDF cache texels to its world region.
+    pub static_camera_params:     UniformBuffer<GpuCameraParams>,
+    pub static_sdf_params:        UniformBuffer<GpuStaticSdfParams>,
+    pub static_sdf_cache:         StaticSdfCache,
 }
 
 impl LightPassPipelineAssets
@@ -69,11 +162,67 @@ impl LightPassPipelineAssets
     pub fn write_buffer(&mut self, device: &RenderDevice, queue: &RenderQueue)
     {
         self.light_sources.write_buffer(device, queue);
+        self.spot_lights.write_buffer(device, queue);
+        self.area_lights.write_buffer(device, queue);
+        self.directional_lights.write_buffer(device, queue);
         self.light_occluders.write_buffer(device, queue);
+        self.polygon_occluders.write_buffer(device, queue);
+        self.occluder_vertices.write_buffer(device, queue);
+        self.occluder_tiles.write_buffer(device, queue);
         self.camera_params.write_buffer(device, queue);
         self.light_pass_params.write_buffer(device, queue);
         self.probes.write_buffer(device, queue);
         self.skylight_masks.write_buffer(device, queue);
+        self.skylight_vertices.write_buffer(device, queue);
+        self.jfa_params.write_buffer(device, queue);
+        self.static_sdf_params.write_buffer(device, queue);
+
+        // Static occluders are only uploaded when the cache is rebuilt.
+        if self.static_sdf_cache.dirty.load(Ordering::Relaxed)
+            || self.static_occluders.buffer().is_none()
+        {
+            self.static_occluders.write_buffer(device, queue);
+            self.static_polygon_occluders.write_buffer(device, queue);
+            self.static_occluder_vertices.write_buffer(device, queue);
+            self.static_occluder_tiles.write_buffer(device, queue);
+            self.static_camera_params.write_buffer(device, queue);
+        }
+    }
+
+    /// Prepares the steps flooding seeds across the whole SDF.
+    pub fn prepare_jfa_steps(&mut self, device: &RenderDevice, queue: &RenderQueue, sdf_size: UVec2)
+    {
+        let first_step = (sdf_size.max_element().next_power_of_two() / 2).max(1);
+        let steps: Vec<u32> =
+            std::iter::successors(Some(first_step), |step| (*step > 1).then_some(step / 2))
+                .collect();
+
+        self.jfa_steps.resize_with(steps.len(), default);
+        for (buffer, step) in self.jfa_steps.iter_mut().zip(steps) {
+            buffer.get_mut().step = step as i32;
+            buffer.write_buffer(device, queue);
+        }
+    }
+
+    /// Allocates one light list per workgroup tile of the probe grid.
+    pub fn prepare_light_tiles(&mut self, device: &RenderDevice, probe_grid: UVec2)
+    {
+        let tiles = (probe_grid / WORKGROUP_SIZE).max(UVec2::ONE);
+        let size = (tiles.x * tiles.y * LIGHT_TILE_STRIDE) as u64 * size_of::<u32>() as u64;
+        if self
+            .light_tiles
+            .as_ref()
+            .is_some_and(|buffer| buffer.size() == size)
+        {
+            return;
+        }
+
+        self.light_tiles = Some(device.create_buffer(&BufferDescriptor {
+            label: Some("gi_light_tiles"),
+            size,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        }));
     }
 }
 
@@ -81,9 +230,12 @@ impl LightPassPipelineAssets
 pub fn system_prepare_pipeline_assets(
     render_device:         Res<RenderDevice>,
     render_queue:          Res<RenderQueue>,
+    target_sizes:          Res<ComputedTargetSizes>,
     mut gi_compute_assets: ResMut<LightPassPipelineAssets>,
 ) {
     gi_compute_assets.write_buffer(&render_device, &render_queue);
+    gi_compute_assets.prepare_light_tiles(&render_device, target_sizes.probe_grid_usize);
+    gi_compute_assets.prepare_jfa_steps(&render_device, &render_queue, target_sizes.sdf_target_usize);
 }
 
 #[rustfmt::skip]
@@ -92,12 +244,17 @@ pub fn system_prepare_pipeline_assets(
 pub fn system_extract_pipeline_assets(
     res_light_settings:         Extract<Res<BevyMagicLight2DSettings>>,
     res_target_sizes:           Extract<Res<ComputedTargetSizes>>,
+    res_light_cookies:          Extract<Res<LightCookieAtlas>>,
+    res_time:                   Extract<Res<Time>>,
 
-    query_lights:               Extract<Query<(&GlobalTransform, &OmniLightSource2D, &InheritedVisibility, &ViewVisibility)>>,
-    query_occluders:            Extract<Query<(&LightOccluder2D, &GlobalTransform, &Transform, &InheritedVisibility, &ViewVisibility)>>,
+    query_lights:               Extract<Query<(Entity, &GlobalTransform, &OmniLightSource2D, Option<&LightCookie2D>, Option<&LightAnimation2D>, &InheritedVisibility, &ViewVisibility)>>,
+    query_spot_lights:          Extract<Query<(&GlobalTransform, &SpotLight2D, Option<&LightCookie2D>, Option<&LightAnimation2D>, &InheritedVisibility, &ViewVisibility)>>,
+    query_line_lights:          Extract<Query<(&GlobalTransform, &LineLight2D, Option<&LightAnimation2D>, &InheritedVisibility, &ViewVisibility)>>,
+    query_area_lights:          Extract<Query<(&GlobalTransform, &AreaLight2D, Option<&LightAnimation2D>, &InheritedVisibility, &ViewVisibility)>>,
     query_camera:               Extract<Query<(&Camera, &GlobalTransform), With<FloorCamera>>>,
     query_masks:                Extract<Query<(&GlobalTransform, &SkylightMask2D)>>,
     query_skylight_light:       Extract<Query<&SkylightLight2D>>,
+    query_directional_lights:   Extract<Query<&DirectionalLight2D>>,
 
     mut gpu_target_sizes:       ResMut<ComputedTargetSizes>,
     mut gpu_pipeline_assets:    ResMut<LightPassPipelineAssets>,
@@ -107,62 +264,155 @@ pub fn system_extract_pipeline_assets(
 
     *gpu_target_sizes = **res_target_sizes;
 
+    let time = res_time.elapsed_secs();
+    let animate = |animation: Option<&LightAnimation2D>, intensity: f32, color: Color, center: Vec2| {
+        animation.map_or((intensity, color, center), |animation| {
+            animation.animate(time, intensity, color, center)
+        })
+    };
+
     {
         let light_sources = gpu_pipeline_assets.light_sources.get_mut();
-        let mut rng = rand::rng();
         light_sources.count = 0;
         light_sources.data.clear();
-        for (transform, light_source, hviz, vviz) in query_lights.iter() {
+        for (entity, transform, light_source, cookie, animation, hviz, vviz) in query_lights.iter() {
             if hviz.get() && vviz.get() {
+                let axis = transform.right().truncate().normalize_or(Vec2::X);
+                let jitter = LightAnimation2D::jitter(
+                    light_animation::entity_seed(entity),
+                    light_source.jitter_intensity,
+                    light_source.jitter_translation,
+                );
+                let (intensity, color, center) = jitter.animate(
+                    time,
+                    light_source.intensity,
+                    light_source.color,
+                    transform.translation().truncate(),
+                );
+                let (intensity, color, center) = animate(animation, intensity, color, center);
                 light_sources.count += 1;
                 light_sources.data.push(GpuOmniLightSource::new(
                     OmniLightSource2D {
-                        intensity: light_source.intensity
-                            + rng.random_range(-1.0..1.0) * light_source.jitter_intensity,
+                        intensity,
+                        color,
                         ..*light_source
                     },
-                    Vec2::new(
-                        transform.translation().x
-                            + rng.random_range(-1.0..1.0) * light_source.jitter_translation,
-                        transform.translation().y
-                            + rng.random_range(-1.0..1.0) * light_source.jitter_translation,
-                    ),
+                    center,
+                    GpuLightCookie::new(cookie, &res_light_cookies, axis),
                 ));
             }
         }
     }
 
     {
-        let light_occluders = gpu_pipeline_assets.light_occluders.get_mut();
-        light_occluders.count = 0;
-        light_occluders.data.clear();
-        for (occluder, global_transform, transform, hviz, vviz) in query_occluders.iter() {
+        let spot_lights = gpu_pipeline_assets.spot_lights.get_mut();
+        spot_lights.count = 0;
+        spot_lights.data.clear();
+        for (transform, spot_light, cookie, animation, hviz, vviz) in query_spot_lights.iter() {
             if hviz.get() && vviz.get() {
-                light_occluders.count += 1;
-                light_occluders.data.push(GpuLightOccluder2D {
-                    center: global_transform.translation().xy(),
-                    rotation: transform.rotation.inverse().into(),
-                    h_extent: occluder.h_size,
-                });
+                let direction = transform.right().truncate().normalize_or(Vec2::X);
+                let (intensity, color, center) = animate(
+                    animation,
+                    spot_light.intensity,
+                    spot_light.color,
+                    transform.translation().truncate(),
+                );
+                spot_lights.count += 1;
+                spot_lights.data.push(GpuSpotLightSource::new(
+                    SpotLight2D {
+                        intensity,
+                        color,
+                        ..*spot_light
+                    },
+                    center,
+                    direction,
+                    GpuLightCookie::new(cookie, &res_light_cookies, direction),
+                ));
+            }
+        }
+    }
+
+    {
+        let area_lights = gpu_pipeline_assets.area_lights.get_mut();
+        area_lights.count = 0;
+        area_lights.data.clear();
+        for (transform, line_light, animation, hviz, vviz) in query_line_lights.iter() {
+            if hviz.get() && vviz.get() {
+                let axis = transform.right().truncate().normalize_or(Vec2::X);
+                let (intensity, color, center) = animate(
+                    animation,
+                    line_light.intensity,
+                    line_light.color,
+                    transform.translation().truncate(),
+                );
+                area_lights.count += 1;
+                area_lights.data.push(GpuAreaLightSource::from_line(
+                    LineLight2D {
+                        intensity,
+                        color,
+                        ..*line_light
+                    },
+                    center,
+                    axis,
+                ));
+            }
+        }
+        for (transform, area_light, animation, hviz, vviz) in query_area_lights.iter() {
+            if hviz.get() && vviz.get() {
+                let axis = transform.right().truncate().normalize_or(Vec2::X);
+                let (intensity, color, center) = animate(
+                    animation,
+                    area_light.intensity,
+                    area_light.color,
+                    transform.translation().truncate(),
+                );
+                area_lights.count += 1;
+                area_lights.data.push(GpuAreaLightSource::from_area(
+                    AreaLight2D {
+                        intensity,
+                        color,
+                        ..*area_light
+                    },
+                    center,
+                    axis,
+                ));
             }
         }
     }
 
     {
+        let directional_lights = gpu_pipeline_assets.directional_lights.get_mut();
+        directional_lights.count = 0;
+        directional_lights.data.clear();
+        for directional_light in query_directional_lights.iter() {
+            if let Some(direction) = directional_light.direction.try_normalize() {
+                directional_lights.count += 1;
+                directional_lights.data.push(GpuDirectionalLight::new(*directional_light, direction));
+            }
+        }
+    }
+
+    {
+        let gpu_pipeline_assets = &mut *gpu_pipeline_assets;
         let skylight_masks = gpu_pipeline_assets.skylight_masks.get_mut();
+        let skylight_vertices = gpu_pipeline_assets.skylight_vertices.get_mut();
         skylight_masks.count = 0;
         skylight_masks.data.clear();
+        skylight_vertices.count = 0;
+        skylight_vertices.data.clear();
         for (transform, mask) in query_masks.iter() {
             skylight_masks.count += 1;
-            skylight_masks.data.push(GpuSkylightMaskData::new(
-                transform.translation().truncate(),
-                mask.h_size,
-            ));
+            skylight_masks.data.push(GpuSkylightMaskData::new(mask, transform, skylight_vertices));
         }
     }
 
     {
         if let Ok((camera, camera_global_transform)) = query_camera.get_single() {
+            // Sprite occluder masks are rendered after the light pass, with
+            // the camera of the previous frame.
+            let mask_view_proj = gpu_pipeline_assets.camera_params.get().view_proj;
+            gpu_pipeline_assets.jfa_params.get_mut().mask_view_proj = mask_view_proj;
+
             let camera_params = gpu_pipeline_assets.camera_params.get_mut();
             let projection = camera.clip_from_view();
             let inverse_projection = projection.inverse();
@@ -180,9 +430,8 @@ pub fn system_extract_pipeline_assets(
                 1.0 / gpu_target_sizes.primary_target_size.y,
             );
 
-            let scale = 2.0;
-            camera_params.sdf_scale     = Vec2::splat(scale);
-            camera_params.inv_sdf_scale = Vec2::splat(1. / scale);
+            camera_params.sdf_scale     = Vec2::splat(SDF_VIEW_SCALE);
+            camera_params.inv_sdf_scale = Vec2::splat(1. / SDF_VIEW_SCALE);
 
             let probes = gpu_pipeline_assets.probes.get_mut();
             probes.data[*gpu_frame_counter as usize].camera_pose =
@@ -207,6 +456,7 @@ pub fn system_extract_pipeline_assets(
         light_pass_params.indirect_light_contrib      = light_pass_config.indirect_light_contrib;
         light_pass_params.indirect_rays_radius_factor = light_pass_config.indirect_rays_radius_factor;
         light_pass_params.indirect_rays_per_sample    = light_pass_config.indirect_rays_per_sample;
+        light_pass_params.emissive_intensity          = light_pass_config.emissive_intensity;
     }
 
     {
@@ -222,3 +472,357 @@ pub fn system_extract_pipeline_assets(
 
     *gpu_frame_counter = (*gpu_frame_counter + 1) % (GI_SCREEN_PROBE_SIZE * GI_SCREEN_PROBE_SIZE);
 }
+
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
+pub fn system_extract_occluders(
+    res_light_settings:      Extract<Res<BevyMagicLight2DSettings>>,
+    res_target_sizes:        Extract<Res<ComputedTargetSizes>>,
+    res_mesh_outlines:       Extract<Res<MeshOccluderOutlines>>,
+
+    query_camera:            Extract<Query<(&Camera, &GlobalTransform), With<FloorCamera>>>,
+    query_occluders:         Extract<Query<(&LightOccluder2D, Option<&TranslucentOccluder2D>, &GlobalTransform, &InheritedVisibility, &ViewVisibility), Or<(Without<StaticOccluder2D>, With<TranslucentOccluder2D>)>>>,
+    query_polygon_occluders: Extract<Query<(&PolygonOccluder2D, Option<&TranslucentOccluder2D>, &GlobalTransform, &InheritedVisibility, &ViewVisibility), Or<(Without<StaticOccluder2D>, With<TranslucentOccluder2D>)>>>,
+    query_mesh_occluders:    Extract<Query<(&MeshOccluder2D, &Mesh2d, Option<&TranslucentOccluder2D>, &GlobalTransform, &InheritedVisibility), Or<(Without<StaticOccluder2D>, With<TranslucentOccluder2D>)>>>,
+    query_sprite_occluders:  Extract<Query<&ViewVisibility, With<MeshMaterial2d<SpriteOccluderMaterial>>>>,
+    query_tile_occluders:    Extract<Query<&ViewVisibility, With<MeshMaterial2d<TileOccluderMaterial>>>>,
+
+    mut gpu_pipeline_assets: ResMut<LightPassPipelineAssets>,
+) {
+    // Jump flooding rasterises occluders on the occluder layer instead.
+    let analytic = res_light_settings.sdf_mode == SdfMode::Analytic;
+
+    {
+        let gpu_pipeline_assets = &mut *gpu_pipeline_assets;
+        let light_occluders = gpu_pipeline_assets.light_occluders.get_mut();
+        light_occluders.count = 0;
+        light_occluders.data.clear();
+        for (occluder, translucent, global_transform, hviz, vviz) in query_occluders.iter() {
+            if analytic && hviz.get() && vviz.get() {
+                light_occluders.count += 1;
+                light_occluders.data.push(GpuLightOccluder2D::new(occluder, translucent, global_transform));
+            }
+        }
+
+    }
+
+    {
+        let gpu_pipeline_assets = &mut *gpu_pipeline_assets;
+        let polygon_occluders = gpu_pipeline_assets.polygon_occluders.get_mut();
+        let occluder_vertices = gpu_pipeline_assets.occluder_vertices.get_mut();
+        polygon_occluders.count = 0;
+        polygon_occluders.data.clear();
+        occluder_vertices.data.clear();
+        for (polygon, translucent, transform, hviz, vviz) in query_polygon_occluders.iter() {
+            if analytic && hviz.get() && vviz.get() {
+                let gpu_polygon = GpuPolygonOccluder::new(polygon.operation, polygon.albedo, polygon.emissive, translucent);
+                push_polygon_occluder(polygon_occluders, occluder_vertices, std::slice::from_ref(&polygon.vertices), gpu_polygon, transform);
+            }
+        }
+        // Meshes are culled against the view, which the SDF extends past.
+        for (occluder, mesh, translucent, transform, hviz) in query_mesh_occluders.iter() {
+            let outline = res_mesh_outlines.get(mesh).filter(|_| analytic && hviz.get());
+            if let Some(outline) = outline {
+                let gpu_polygon = GpuPolygonOccluder::new(occluder.operation, occluder.albedo, occluder.emissive, translucent);
+                push_polygon_occluder(polygon_occluders, occluder_vertices, outline, gpu_polygon, transform);
+            }
+        }
+        occluder_vertices.count = occluder_vertices.data.len() as u32;
+    }
+
+    {
+        let gpu_pipeline_assets = &mut *gpu_pipeline_assets;
+        let occluder_tiles = gpu_pipeline_assets.occluder_tiles.get_mut();
+        if let Ok((camera, camera_global_transform)) = query_camera.get_single() {
+            let view_proj = camera.clip_from_view() * camera_global_transform.compute_matrix().inverse();
+            bin_occluders(
+                gpu_pipeline_assets.light_occluders.get(),
+                gpu_pipeline_assets.polygon_occluders.get(),
+                view_proj,
+                SDF_VIEW_SCALE,
+                res_target_sizes.sdf_target_usize,
+                occluder_tiles,
+            );
+        } else {
+            occluder_tiles.build(UVec2::ZERO, 0.0, &[]);
+        }
+    }
+
+    gpu_pipeline_assets.sdf_mode = res_light_settings.sdf_mode;
+    gpu_pipeline_assets.jfa_enabled = !analytic
+        || query_sprite_occluders.iter().any(|vviz| vviz.get())
+        || query_tile_occluders.iter().any(|vviz| vviz.get());
+    gpu_pipeline_assets.jfa_params.get_mut().merge_analytic = analytic as u32;
+}
+
+/// Rebuilds the static occluder buffers when a static occluder changes, the
+/// SDF leaves the region covered by the static SDF cache or the targets are
+/// recreated.
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
+pub fn system_extract_static_occluders(
+    res_light_settings:      Extract<Res<BevyMagicLight2DSettings>>,
+    res_target_sizes:        Extract<Res<ComputedTargetSizes>>,
+    res_mesh_outlines:       Extract<Res<MeshOccluderOutlines>>,
+    res_gi_targets:          Extract<Res<GiTargetsWrapper>>,
+
+    query_camera:            Extract<Query<(&Camera, &GlobalTransform), With<FloorCamera>>>,
+    query_occluders:         Extract<Query<(Ref<LightOccluder2D>, Ref<GlobalTransform>, Ref<InheritedVisibility>, Ref<StaticOccluder2D>), Without<TranslucentOccluder2D>>>,
+    query_polygon_occluders: Extract<Query<(Ref<PolygonOccluder2D>, Ref<GlobalTransform>, Ref<InheritedVisibility>, Ref<StaticOccluder2D>), Without<TranslucentOccluder2D>>>,
+    query_mesh_occluders:    Extract<Query<(Ref<MeshOccluder2D>, Ref<Mesh2d>, Ref<GlobalTransform>, Ref<InheritedVisibility>, Ref<StaticOccluder2D>), Without<TranslucentOccluder2D>>>,
+
+    mut gpu_pipeline_assets: ResMut<LightPassPipelineAssets>,
+) {
+    let gpu_pipeline_assets = &mut *gpu_pipeline_assets;
+    let cache = &mut gpu_pipeline_assets.static_sdf_cache;
+
+    // Jump flooding rasterises static occluders like any other, forget the
+    // cache so it's rebuilt when switching back.
+    if res_light_settings.sdf_mode != SdfMode::Analytic {
+        cache.region = Rect::default();
+        return;
+    }
+    let Ok((camera, camera_global_transform)) = query_camera.get_single() else {
+        return;
+    };
+
+    let view_proj = camera.clip_from_view() * camera_global_transform.compute_matrix().inverse();
+    let inverse_view_proj = view_proj.inverse();
+    let sdf_bounds = [
+        Vec2::new(-1.0, -1.0),
+        Vec2::new(1.0, -1.0),
+        Vec2::new(1.0, 1.0),
+        Vec2::new(-1.0, 1.0),
+    ]
+    .map(|ndc| inverse_view_proj.project_point3((ndc * SDF_VIEW_SCALE).extend(0.0)).truncate())
+    .iter()
+    .fold(Rect { min: Vec2::MAX, max: Vec2::MIN }, |bounds, &corner| bounds.union_point(corner));
+
+    // The cache is also rebuilt when zooming in far enough to lose precision.
+    let region_valid = cache.region.contains(sdf_bounds.min)
+        && cache.region.contains(sdf_bounds.max)
+        && cache.region.width() <= sdf_bounds.width() * STATIC_SDF_SCALE * 2.0
+        && cache.region.height() <= sdf_bounds.height() * STATIC_SDF_SCALE * 2.0;
+
+    let occluder_count = query_occluders.iter().len() + query_polygon_occluders.iter().len() + query_mesh_occluders.iter().len();
+    let occluders_changed = occluder_count != cache.occluder_count
+        || query_occluders.iter().any(|(occluder, global_transform, hviz, marker)| {
+            occluder.is_changed() || global_transform.is_changed() || hviz.is_changed() || marker.is_changed()
+        })
+        || query_polygon_occluders.iter().any(|(polygon, global_transform, hviz, marker)| {
+            polygon.is_changed() || global_transform.is_changed() || hviz.is_changed() || marker.is_changed()
+        })
+        || (res_mesh_outlines.is_changed() && !query_mesh_occluders.is_empty())
+        || query_mesh_occluders.iter().any(|(occluder, mesh, global_transform, hviz, marker)| {
+            occluder.is_changed() || mesh.is_changed() || global_transform.is_changed() || hviz.is_changed() || marker.is_changed()
+        });
+
+    if region_valid
+        && !occluders_changed
+        && cache.sdf_size == res_target_sizes.static_sdf_target_usize
+        && cache.generation == res_gi_targets.generation
+    {
+        return;
+    }
+
+    cache.region = Rect::from_center_half_size(sdf_bounds.center(), sdf_bounds.half_size() * STATIC_SDF_SCALE);
+    cache.sdf_size = res_target_sizes.static_sdf_target_usize;
+    cache.generation = res_gi_targets.generation;
+    cache.occluder_count = occluder_count;
+    cache.dirty.store(true, Ordering::Relaxed);
+
+    let static_occluders = gpu_pipeline_assets.static_occluders.get_mut();
+    static_occluders.count = 0;
+    static_occluders.data.clear();
+    for (occluder, global_transform, hviz, _) in query_occluders.iter() {
+        if hviz.get() {
+            static_occluders.count += 1;
+            static_occluders.data.push(GpuLightOccluder2D::new(&occluder, None, &global_transform));
+        }
+    }
+
+    let static_polygon_occluders = gpu_pipeline_assets.static_polygon_occluders.get_mut();
+    let static_occluder_vertices = gpu_pipeline_assets.static_occluder_vertices.get_mut();
+    static_polygon_occluders.count = 0;
+    static_polygon_occluders.data.clear();
+    static_occluder_vertices.data.clear();
+    for (polygon, global_transform, hviz, _) in query_polygon_occluders.iter() {
+        if hviz.get() {
+            let gpu_polygon = GpuPolygonOccluder::new(polygon.operation, polygon.albedo, polygon.emissive, None);
+            push_polygon_occluder(static_polygon_occluders, static_occluder_vertices, std::slice::from_ref(&polygon.vertices), gpu_polygon, &global_transform);
+        }
+    }
+    for (occluder, mesh, global_transform, hviz, _) in query_mesh_occluders.iter() {
+        let outline = res_mesh_outlines.get(&mesh).filter(|_| hviz.get());
+        if let Some(outline) = outline {
+            let gpu_polygon = GpuPolygonOccluder::new(occluder.operation, occluder.albedo, occluder.emissive, None);
+            push_polygon_occluder(static_polygon_occluders, static_occluder_vertices, outline, gpu_polygon, &global_transform);
+        }
+    }
+    static_occluder_vertices.count = static_occluder_vertices.data.len() as u32;
+
+    // The cache is an axis aligned view of its region, with the SDF texel
+    // mapping of the main view and no extra SDF scale.
+    let region_inverse_view_proj = Mat4::from_translation(cache.region.center().extend(0.0))
+        * Mat4::from_scale(cache.region.half_size().extend(1.0));
+    let region_view_proj = region_inverse_view_proj.inverse();
+    *gpu_pipeline_assets.static_camera_params.get_mut() = GpuCameraParams {
+        view_proj:         region_view_proj,
+        inverse_view_proj: region_inverse_view_proj,
+        sdf_scale:         Vec2::ONE,
+        inv_sdf_scale:     Vec2::ONE,
+        ..default()
+    };
+
+    bin_occluders(
+        static_occluders,
+        static_polygon_occluders,
+        region_view_proj,
+        1.0,
+        cache.sdf_size,
+        gpu_pipeline_assets.static_occluder_tiles.get_mut(),
+    );
+
+    *gpu_pipeline_assets.static_sdf_params.get_mut() = GpuStaticSdfParams {
+        bounds_min: cache.region.min,
+        bounds_max: cache.region.max,
+        enabled:    (static_occluders.count + static_polygon_occluders.count > 0) as u32,
+    };
+}
+
+/// Pushes the contours of a polygon to consecutive entries, the first one
+/// holding the bounds of all contours.
+fn push_polygon_occluder(
+    polygon_occluders: &mut GpuPolygonOccluderBuffer,
+    occluder_vertices: &mut GpuOccluderVertexBuffer,
+    contours: &[Vec<Vec2>],
+    polygon: GpuPolygonOccluder,
+    transform: &GlobalTransform,
+)
+{
+    let first = polygon_occluders.data.len();
+    for contour in contours.iter().filter(|contour| contour.len() >= 3) {
+        let vertex_offset = occluder_vertices.data.len();
+        occluder_vertices.data.extend(
+            contour
+                .iter()
+                .map(|vertex| transform.transform_point(vertex.extend(0.0)).truncate()),
+        );
+        let vertices = &occluder_vertices.data[vertex_offset..];
+        polygon_occluders.data.push(GpuPolygonOccluder {
+            bounds_min: vertices.iter().copied().fold(Vec2::MAX, Vec2::min),
+            bounds_max: vertices.iter().copied().fold(Vec2::MIN, Vec2::max),
+            vertex_offset: vertex_offset as u32,
+            vertex_count: vertices.len() as u32,
+            ..polygon.clone()
+        });
+    }
+
+    let contours = &mut polygon_occluders.data[first..];
+    let Some(bounds_min) = contours
+        .iter()
+        .map(|contour| contour.bounds_min)
+        .reduce(Vec2::min)
+    else {
+        return;
+    };
+    let bounds_max = contours
+        .iter()
+        .map(|contour| contour.bounds_max)
+        .fold(Vec2::MIN, Vec2::max);
+    contours[0].bounds_min = bounds_min;
+    contours[0].bounds_max = bounds_max;
+    contours[0].contour_count = contours.len() as u32;
+    polygon_occluders.count = polygon_occluders.data.len() as u32;
+}
+
+/// Bins occluders into tiles of `OCCLUDER_TILE_SIZE` SDF texels. Occluders are
+/// listed in the tiles their bounds overlap and the adjacent ones, so the SDF
+/// is exact up to one tile away from any occluder and clamped beyond. Polygons
+/// are listed by their first entry, offset by the number of light occluders.
+fn bin_occluders(
+    light_occluders: &GpuLightOccluderBuffer,
+    polygon_occluders: &GpuPolygonOccluderBuffer,
+    view_proj: Mat4,
+    sdf_scale: f32,
+    sdf_size: UVec2,
+    occluder_tiles: &mut GpuOccluderTileBuffer,
+)
+{
+    let tile_count = (sdf_size + OCCLUDER_TILE_SIZE - 1) / OCCLUDER_TILE_SIZE;
+    if tile_count.cmpeq(UVec2::ZERO).any() {
+        occluder_tiles.build(UVec2::ZERO, 0.0, &[]);
+        return;
+    }
+
+    let sdf_size = sdf_size.as_vec2();
+    let world_to_texel = |world_pose: Vec2| {
+        let ndc = view_proj.project_point3(world_pose.extend(0.0)).truncate() / sdf_scale;
+        Vec2::new(ndc.x + 1.0, 1.0 - ndc.y) * 0.5 * sdf_size
+    };
+
+    // Tiles further than one tile from an occluder are at least this far.
+    let inverse_view_proj = view_proj.inverse();
+    let texel_ndc = 2.0 * sdf_scale / sdf_size;
+    let ndc_to_world = |ndc: Vec2| inverse_view_proj.project_point3(ndc.extend(0.0)).truncate();
+    let texel_world = Vec2::new(
+        ndc_to_world(Vec2::new(texel_ndc.x, 0.0)).distance(ndc_to_world(Vec2::ZERO)),
+        ndc_to_world(Vec2::new(0.0, texel_ndc.y)).distance(ndc_to_world(Vec2::ZERO)),
+    );
+    let max_distance = OCCLUDER_TILE_SIZE as f32 * texel_world.min_element();
+
+    let tile_size = OCCLUDER_TILE_SIZE as f32;
+    let max_tile = (tile_count - 1).as_ivec2();
+
+    // World bounds of the occluders, grown by the distance their blending
+    // reaches.
+    let occluder_bounds = light_occluders.data.iter().map(|occluder| {
+        let h_bounds = occluder.h_bounds() + occluder.blend_radius;
+        (occluder.center - h_bounds, occluder.center + h_bounds)
+    });
+    let polygon_bounds = polygon_occluders.data.iter().map(|polygon| {
+        (
+            polygon.bounds_min - polygon.blend_radius,
+            polygon.bounds_max + polygon.blend_radius,
+        )
+    });
+    let first_polygon = light_occluders.data.len();
+
+    let ranges: Vec<(u32, UVec2, UVec2)> = occluder_bounds
+        .chain(polygon_bounds)
+        .enumerate()
+        .filter(|(index, _)| {
+            *index < first_polygon
+                || polygon_occluders.data[index - first_polygon].contour_count > 0
+        })
+        .filter_map(|(index, (world_min, world_max))| {
+            let (texel_min, texel_max) = [
+                world_min,
+                Vec2::new(world_max.x, world_min.y),
+                world_max,
+                Vec2::new(world_min.x, world_max.y),
+            ]
+            .map(world_to_texel)
+            .iter()
+            .fold((Vec2::MAX, Vec2::MIN), |(min, max), &texel| {
+                (min.min(texel), max.max(texel))
+            });
+
+            let tile_min = (texel_min / tile_size - 1.0).floor().as_ivec2();
+            let tile_max = (texel_max / tile_size + 1.0).floor().as_ivec2();
+            if tile_max.cmplt(IVec2::ZERO).any() || tile_min.cmpgt(max_tile).any() {
+                return None;
+            }
+
+            Some((
+                index as u32,
+                tile_min.clamp(IVec2::ZERO, max_tile).as_uvec2(),
+                tile_max.clamp(IVec2::ZERO, max_tile).as_uvec2(),
+            ))
+        })
+        .collect();
+
+    occluder_tiles.build(tile_count, max_distance, &ranges);
+}
@@ -3,12 +3,22 @@ use bevy::render::view::Layer;
 pub const LAYER_FLOOR_ID: Layer = 1;
 pub const LAYER_WALLS_ID: Layer = 2;
 pub const LAYER_OBJECTS_ID: Layer = 3;
+pub const LAYER_EMISSIVE_ID: Layer = 4;
+/// Layer of the sprite occluder masks, rendered by the plugin occluder camera.
+pub const LAYER_OCCLUDER_ID: Layer = 5;
 
 pub const CAMERA_LAYER_FLOOR: &[Layer] = &[LAYER_FLOOR_ID];
 pub const CAMERA_LAYER_WALLS: &[Layer] = &[LAYER_WALLS_ID];
 pub const CAMERA_LAYER_OBJECTS: &[Layer] = &[LAYER_OBJECTS_ID];
+pub const CAMERA_LAYER_EMISSIVE: &[Layer] = &[LAYER_EMISSIVE_ID];
+pub const CAMERA_LAYER_OCCLUDER: &[Layer] = &[LAYER_OCCLUDER_ID];
 
-pub const ALL_LAYERS: &[Layer] = &[LAYER_FLOOR_ID, LAYER_WALLS_ID, LAYER_OBJECTS_ID];
+pub const ALL_LAYERS: &[Layer] = &[
+    LAYER_FLOOR_ID,
+    LAYER_WALLS_ID,
+    LAYER_OBJECTS_ID,
+    LAYER_EMISSIVE_ID,
+];
 
 pub const LAYER_POST_PROCESSING_ID: Layer = 42;
 pub const CAMERA_LAYER_POST_PROCESSING: &[Layer] = &[LAYER_POST_PROCESSING_ID];
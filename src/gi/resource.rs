@@ -4,7 +4,7 @@ use bevy_inspector_egui::prelude::ReflectInspectorOptions;
 #[cfg(feature = "egui")]
 use bevy_inspector_egui::InspectorOptions;
 
-use crate::gi::constants::GI_SCREEN_PROBE_SIZE;
+use crate::gi::constants::{GI_SCREEN_PROBE_SIZE, STATIC_SDF_SCALE};
 use crate::gi::util;
 
 #[derive(Copy, Clone, Reflect)]
@@ -22,11 +22,26 @@ impl Default for TargetScalingParams
     }
 }
 
+/// How the occluder SDF is built.
+#[derive(Reflect, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SdfMode
+{
+    /// Every SDF texel evaluates every occluder exactly, cost grows with the
+    /// number of occluders.
+    #[default]
+    Analytic,
+    /// Occluders are rasterised into a mask and distances are propagated with
+    /// jump flooding, cost is independent of the number of occluders but
+    /// distances are only texel accurate.
+    JumpFlood,
+}
+
 #[derive(Resource, Default, Copy, Clone, Reflect)]
 pub struct BevyMagicLight2DSettings
 {
     pub light_pass_params:     LightPassParams,
     pub target_scaling_params: TargetScalingParams,
+    pub sdf_mode:              SdfMode,
 }
 
 #[rustfmt::skip]
@@ -57,6 +72,10 @@ pub struct LightPassParams {
 
     #[cfg_attr(feature = "egui", inspector(min = 1.0, max = 100.0))]
     pub indirect_rays_radius_factor: f32,
+
+    /// Multiplier for radiance injected from the emissive camera target.
+    #[cfg_attr(feature = "egui", inspector(min = 0.0, max = 100.0))]
+    pub emissive_intensity: f32,
 }
 
 impl Default for LightPassParams
@@ -70,6 +89,7 @@ impl Default for LightPassParams
             indirect_light_contrib:      0.5,
             indirect_rays_per_sample:    32,
             indirect_rays_radius_factor: 3.5,
+            emissive_intensity:          1.0,
         }
     }
 }
@@ -85,6 +105,10 @@ pub struct ComputedTargetSizes
     pub sdf_target_isize: IVec2,
     pub sdf_target_usize: UVec2,
 
+    pub static_sdf_target_size:  Vec2,
+    pub static_sdf_target_isize: IVec2,
+    pub static_sdf_target_usize: UVec2,
+
     pub probe_grid_size:  Vec2,
     pub probe_grid_isize: IVec2,
     pub probe_grid_usize: UVec2,
@@ -110,6 +134,11 @@ impl ComputedTargetSizes
             util::align_to_work_group_grid(sizes.sdf_target_size.ceil().as_ivec2());
         sizes.sdf_target_usize = sizes.sdf_target_isize.as_uvec2();
 
+        sizes.static_sdf_target_size = sizes.sdf_target_size * STATIC_SDF_SCALE;
+        sizes.static_sdf_target_isize =
+            util::align_to_work_group_grid(sizes.static_sdf_target_size.ceil().as_ivec2());
+        sizes.static_sdf_target_usize = sizes.static_sdf_target_isize.as_uvec2();
+
         sizes.probe_grid_isize = util::align_to_work_group_grid(
             (sizes.primary_target_size / (GI_SCREEN_PROBE_SIZE as f32))
                 .ceil()
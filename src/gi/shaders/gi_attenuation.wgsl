@@ -1,5 +1,12 @@
 #define_import_path bevy_magic_light_2d::gi_attenuation
 
+#import bevy_magic_light_2d::gi_types::LightFalloff
+
+const FALLOFF_INVERSE_SQUARE: u32 = 0u;
+const FALLOFF_LINEAR:         u32 = 1u;
+const FALLOFF_SMOOTH:         u32 = 2u;
+const FALLOFF_CUSTOM:         u32 = 3u;
+
 fn distance_squared_two(a: vec2<f32>, b: vec2<f32>) -> f32 {
     let c = a - b;
     return dot(c, c);
@@ -37,4 +44,54 @@ fn light_attenuation_at_dist_r(
 ) -> f32 {
     let att       = a / (b + c * d);
     return clamp(att, 0.0, 1000.0);
-}
\ No newline at end of file
+}
+
+// Attenuation of a light at the sample, zero past the falloff radius.
+fn light_attenuation(
+    sample_pose: vec2<f32>,
+    light_pose:  vec2<f32>,
+    falloff:     LightFalloff,
+) -> f32 {
+    let d      = distance(light_pose, sample_pose);
+    let radius = falloff.params.w;
+    if d >= radius {
+        return 0.0;
+    }
+
+    switch falloff.model {
+        case FALLOFF_INVERSE_SQUARE: {
+            let r = falloff.params.x / max(d, falloff.params.x);
+            return r * r;
+        }
+        case FALLOFF_LINEAR: {
+            return 1.0 - d / radius;
+        }
+        case FALLOFF_SMOOTH: {
+            let ref_two = falloff.params.x * falloff.params.x;
+            let x       = d / radius;
+            let window  = clamp(1.0 - x * x * x * x, 0.0, 1.0);
+            return window * window * ref_two / (ref_two + d * d);
+        }
+        case FALLOFF_CUSTOM, default: {
+            let att = falloff.params.x / (falloff.params.y + falloff.params.z * d * d);
+            return clamp(att, 0.0, 1000.0);
+        }
+    }
+}
+
+fn spot_cone_attenuation(
+    sample_pose: vec2<f32>,
+    light_pose:  vec2<f32>,
+    direction:   vec2<f32>,
+    cos_inner:   f32,
+    cos_outer:   f32,
+) -> f32 {
+    let to_sample = sample_pose - light_pose;
+    let len       = length(to_sample);
+    if len <= 0.0 {
+        return 1.0;
+    }
+    let cos_angle = dot(to_sample / len, direction);
+    let edge      = max(cos_inner - cos_outer, 1e-4);
+    return clamp((cos_angle - cos_outer) / edge, 0.0, 1.0);
+}
@@ -79,6 +79,13 @@ fn bilinear_sample_r(t: texture_2d<f32>, s: sampler, uv: vec2<f32>) -> f32 {
     return bilinear_filter(texels, scaled_uv);
 }
 
+fn bilinear_sample_a(t: texture_2d<f32>, s: sampler, uv: vec2<f32>) -> f32 {
+    let texels = textureGather(3, t, s, uv);
+    let dims = textureDimensions(t);
+    let scaled_uv = uv * vec2<f32>(dims);
+    return bilinear_filter(texels, scaled_uv);
+}
+
 fn bilinear_sample_rgba(t: texture_2d<f32>, s: sampler, uv: vec2<f32>) -> vec4<f32> {
     let dims = textureDimensions(t);
     let scaled_uv = uv * vec2<f32>(dims);
@@ -0,0 +1,72 @@
+#import bevy_magic_light_2d::gi_types::{JfaParams, JFA_NO_SEED, JFA_MAX_DISTANCE, pack_material}
+#import bevy_magic_light_2d::gi_camera::{CameraParams, sdf_uv_to_world}
+
+@group(0) @binding(0) var<uniform> camera_params: CameraParams;
+@group(0) @binding(1) var          jfa_in:        texture_storage_2d<rgba32float, read>;
+@group(0) @binding(2) var          sdf_out:       texture_storage_2d<r16float, read_write>;
+@group(0) @binding(3) var<uniform> jfa_params:    JfaParams;
+@group(0) @binding(4) var          transmittance_out: texture_storage_2d<rgba16float, read_write>;
+@group(0) @binding(5) var          material_out:  texture_storage_2d<rgba32uint, write>;
+
+// Rasterised occluders have no material, their surface is white and doesn't
+// emit light.
+fn jfa_material() -> vec4<u32> {
+    return pack_material(vec3<f32>(1.0), vec3<f32>(0.0));
+}
+
+fn texel_to_world(texel: vec2<f32>, dims: vec2<f32>) -> vec2<f32> {
+    return sdf_uv_to_world((texel + 0.5) / dims,
+        camera_params.inverse_view_proj,
+        camera_params.sdf_scale);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let texel_pos = vec2<i32>(invocation_id.xy);
+    let dims      = vec2<f32>(textureDimensions(sdf_out));
+    let seeds     = textureLoad(jfa_in, texel_pos);
+
+    // Nothing occludes in the mask, keep the analytic distance if any.
+    if seeds.x == JFA_NO_SEED {
+        if jfa_params.merge_analytic == 0u {
+            textureStore(sdf_out, texel_pos, vec4<f32>(JFA_MAX_DISTANCE, 0.0, 0.0, 0.0));
+            textureStore(transmittance_out, texel_pos, vec4<f32>(1.0, 1.0, 1.0, JFA_MAX_DISTANCE));
+            textureStore(material_out, texel_pos, jfa_material());
+        }
+        return;
+    }
+
+    let p          = vec2<f32>(texel_pos);
+    let world_pose = texel_to_world(p, dims);
+
+    // Seeds are texel centres, the mask edge lies half a texel closer.
+    let half_texel = 0.5 * min(
+        distance(texel_to_world(p + vec2<f32>(1.0, 0.0), dims), world_pose),
+        distance(texel_to_world(p + vec2<f32>(0.0, 1.0), dims), world_pose),
+    );
+
+    var sdf_mask = distance(texel_to_world(seeds.xy, dims), world_pose) - half_texel;
+    if all(seeds.xy == p) {
+        sdf_mask = -half_texel;
+        if seeds.z != JFA_NO_SEED {
+            sdf_mask = half_texel - distance(texel_to_world(seeds.zw, dims), world_pose);
+        }
+    }
+
+    // Rasterised occluders are opaque, the transmittance of the analytic
+    // translucent occluders is kept.
+    var transmittance = vec4<f32>(1.0, 1.0, 1.0, sdf_mask);
+    var closest       = true;
+    if jfa_params.merge_analytic != 0u {
+        transmittance = textureLoad(transmittance_out, texel_pos);
+        transmittance.a = min(transmittance.a, sdf_mask);
+        let sdf_analytic = textureLoad(sdf_out, texel_pos).r;
+        closest  = sdf_mask < sdf_analytic;
+        sdf_mask = min(sdf_analytic, sdf_mask);
+    }
+    if closest {
+        textureStore(material_out, texel_pos, jfa_material());
+    }
+    textureStore(sdf_out, texel_pos, vec4<f32>(sdf_mask, 0.0, 0.0, 0.0));
+    textureStore(transmittance_out, texel_pos, transmittance);
+}
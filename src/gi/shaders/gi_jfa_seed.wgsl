@@ -0,0 +1,36 @@
+#import bevy_magic_light_2d::gi_types::{JfaParams, JFA_NO_SEED}
+#import bevy_magic_light_2d::gi_camera::{CameraParams, sdf_uv_to_world, world_to_sdf_uv}
+
+@group(0) @binding(0) var<uniform> camera_params: CameraParams;
+@group(0) @binding(1) var<uniform> jfa_params:    JfaParams;
+@group(0) @binding(2) var          occluder_mask: texture_2d<f32>;
+@group(0) @binding(3) var          jfa_out:       texture_storage_2d<rgba32float, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let texel_pos = vec2<i32>(invocation_id.xy);
+    let dims      = textureDimensions(jfa_out);
+    let uv        = (vec2<f32>(texel_pos) + 0.5) / vec2<f32>(dims);
+
+    // The mask is rendered after the light pass, so it is reprojected from
+    // the camera of the previous frame.
+    let world_pose = sdf_uv_to_world(uv,
+        camera_params.inverse_view_proj,
+        camera_params.sdf_scale);
+    let mask_uv    = world_to_sdf_uv(world_pose,
+        jfa_params.mask_view_proj,
+        camera_params.inv_sdf_scale);
+    let mask_dims  = vec2<i32>(textureDimensions(occluder_mask));
+    let mask_pos   = vec2<i32>(floor(mask_uv * vec2<f32>(mask_dims)));
+
+    // Masks write opaque texels only, the alpha threshold is applied there.
+    var occupied = false;
+    if all(mask_pos >= vec2<i32>(0)) && all(mask_pos < mask_dims) {
+        occupied = textureLoad(occluder_mask, mask_pos, 0).a > 0.5;
+    }
+
+    let self_seed = vec2<f32>(texel_pos);
+    let no_seed   = vec2<f32>(JFA_NO_SEED);
+    let seeds     = select(vec4<f32>(no_seed, self_seed), vec4<f32>(self_seed, no_seed), occupied);
+    textureStore(jfa_out, texel_pos, seeds);
+}
@@ -0,0 +1,41 @@
+#import bevy_magic_light_2d::gi_types::{JfaStepParams, JFA_NO_SEED}
+
+@group(0) @binding(0) var<uniform> jfa_params: JfaStepParams;
+@group(0) @binding(1) var          jfa_in:     texture_storage_2d<rgba32float, read>;
+@group(0) @binding(2) var          jfa_out:    texture_storage_2d<rgba32float, write>;
+
+fn nearest_seed(p: vec2<f32>, best: vec2<f32>, candidate: vec2<f32>) -> vec2<f32> {
+    if candidate.x == JFA_NO_SEED {
+        return best;
+    }
+    if best.x == JFA_NO_SEED {
+        return candidate;
+    }
+    let d_best      = p - best;
+    let d_candidate = p - candidate;
+    return select(best, candidate, dot(d_candidate, d_candidate) < dot(d_best, d_best));
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let texel_pos = vec2<i32>(invocation_id.xy);
+    let dims      = vec2<i32>(textureDimensions(jfa_in));
+    let p         = vec2<f32>(texel_pos);
+
+    var seeds = textureLoad(jfa_in, texel_pos);
+    for (var y: i32 = -1; y <= 1; y++) {
+        for (var x: i32 = -1; x <= 1; x++) {
+            let sample_pos = texel_pos + vec2<i32>(x, y) * jfa_params.step;
+            if (x == 0 && y == 0) || any(sample_pos < vec2<i32>(0)) || any(sample_pos >= dims) {
+                continue;
+            }
+            let sample = textureLoad(jfa_in, sample_pos);
+            seeds = vec4<f32>(
+                nearest_seed(p, seeds.xy, sample.xy),
+                nearest_seed(p, seeds.zw, sample.zw),
+            );
+        }
+    }
+
+    textureStore(jfa_out, texel_pos, seeds);
+}
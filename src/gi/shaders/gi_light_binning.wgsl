@@ -0,0 +1,116 @@
+#import bevy_magic_light_2d::gi_types::{LightPassParams, LightSourceBuffer, SpotLightSourceBuffer, AreaLightSourceBuffer, LIGHT_TILE_MAX_LIGHTS, LIGHT_TILE_STRIDE, LIGHT_KIND_SHIFT, LIGHT_KIND_OMNI, LIGHT_KIND_SPOT, LIGHT_KIND_AREA}
+#import bevy_magic_light_2d::gi_camera::{CameraParams, screen_to_world}
+
+@group(0) @binding(0) var<uniform>             camera_params:        CameraParams;
+@group(0) @binding(1) var<uniform>             cfg:                  LightPassParams;
+@group(0) @binding(2) var<storage>             lights_source_buffer: LightSourceBuffer;
+@group(0) @binding(3) var<storage>             spot_lights_buffer:   SpotLightSourceBuffer;
+@group(0) @binding(4) var<storage>             area_lights_buffer:   AreaLightSourceBuffer;
+@group(0) @binding(5) var<storage, read_write> light_tiles:          array<u32>;
+
+const TILE_SIZE:    i32 = 8;
+const TILE_THREADS: u32 = 64u;
+
+var<workgroup> tile_light_count: u32;
+var<workgroup> tile_scan:        array<u32, TILE_THREADS>;
+
+fn tile_overlaps(tile_min: vec2<f32>, tile_max: vec2<f32>, center: vec2<f32>, reach: f32) -> bool {
+    let closest = clamp(center, tile_min, tile_max);
+    return distance(closest, center) < reach;
+}
+
+// Each workgroup bins the lights reaching one tile of probes, the tile grid
+// matches the workgroups of the probe pass.
+@compute @workgroup_size(8, 8, 1)
+fn main(
+    @builtin(workgroup_id)           workgroup_id:    vec3<u32>,
+    @builtin(num_workgroups)         num_workgroups:  vec3<u32>,
+    @builtin(local_invocation_index) local_index:     u32,
+) {
+    if local_index == 0u {
+        tile_light_count = 0u;
+    }
+    workgroupBarrier();
+
+    let tile_index  = workgroup_id.y * num_workgroups.x + workgroup_id.x;
+    let tile_offset = tile_index * LIGHT_TILE_STRIDE;
+
+    // World-space bounds of the probes in the tile, grown by the probe size
+    // to account for the probe jitter.
+    let tile_origin_screen = vec2<i32>(workgroup_id.xy) * TILE_SIZE * cfg.probe_size;
+    let tile_end_screen    = tile_origin_screen + TILE_SIZE * cfg.probe_size;
+    let corner_a = screen_to_world(
+        tile_origin_screen,
+        camera_params.screen_size,
+        camera_params.inverse_view_proj,
+        camera_params.screen_size_inv,
+    );
+    let corner_b = screen_to_world(
+        tile_end_screen,
+        camera_params.screen_size,
+        camera_params.inverse_view_proj,
+        camera_params.screen_size_inv,
+    );
+    let probe_size_f32 = f32(cfg.probe_size);
+    let tile_min       = min(corner_a, corner_b) - probe_size_f32;
+    let tile_max       = max(corner_a, corner_b) + probe_size_f32;
+
+    let omni_count  = lights_source_buffer.count;
+    let spot_count  = spot_lights_buffer.count;
+    let area_count  = area_lights_buffer.count;
+    let total_count = omni_count + spot_count + area_count;
+
+    // Each thread tests one light of a chunk, a prefix sum over the chunk gives
+    // the slots of the lights reaching the tile. Lists keep the order of the
+    // light buffers, so the lights dropped past `LIGHT_TILE_MAX_LIGHTS` don't
+    // change from frame to frame.
+    for (var chunk: u32 = 0u; chunk < total_count; chunk += TILE_THREADS) {
+        let i     = chunk + local_index;
+        var hit   = false;
+        var entry = 0u;
+        if i < omni_count {
+            let light = lights_source_buffer.data[i];
+            hit   = tile_overlaps(tile_min, tile_max, light.center, light.falloff.params.w);
+            entry = (LIGHT_KIND_OMNI << LIGHT_KIND_SHIFT) | i;
+        } else if i < omni_count + spot_count {
+            let index = i - omni_count;
+            let light = spot_lights_buffer.data[index];
+            hit   = tile_overlaps(tile_min, tile_max, light.center, light.falloff.params.w);
+            entry = (LIGHT_KIND_SPOT << LIGHT_KIND_SHIFT) | index;
+        } else if i < total_count {
+            let index = i - omni_count - spot_count;
+            let light = area_lights_buffer.data[index];
+            let reach = light.falloff.params.w + length(light.h_extent);
+            hit   = tile_overlaps(tile_min, tile_max, light.center, reach);
+            entry = (LIGHT_KIND_AREA << LIGHT_KIND_SHIFT) | index;
+        }
+
+        // Inclusive prefix sum of the hits of the chunk.
+        tile_scan[local_index] = u32(hit);
+        workgroupBarrier();
+        for (var offset: u32 = 1u; offset < TILE_THREADS; offset <<= 1u) {
+            var sum = tile_scan[local_index];
+            if local_index >= offset {
+                sum += tile_scan[local_index - offset];
+            }
+            workgroupBarrier();
+            tile_scan[local_index] = sum;
+            workgroupBarrier();
+        }
+
+        let slot = tile_light_count + tile_scan[local_index] - u32(hit);
+        if hit && slot < LIGHT_TILE_MAX_LIGHTS {
+            light_tiles[tile_offset + 1u + slot] = entry;
+        }
+        workgroupBarrier();
+
+        if local_index == 0u {
+            tile_light_count += tile_scan[TILE_THREADS - 1u];
+        }
+        workgroupBarrier();
+    }
+
+    if local_index == 0u {
+        light_tiles[tile_offset] = min(tile_light_count, LIGHT_TILE_MAX_LIGHTS);
+    }
+}
@@ -0,0 +1,20 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+#import bevy_magic_light_2d::gi_occluder_shapes::sdf_shape
+
+struct OccluderShapeParams {
+    h_extent: vec2<f32>,
+    shape:    u32,
+    radius:   f32,
+}
+
+@group(2) @binding(0) var<uniform> params: OccluderShapeParams;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The quad spans the occluder box, uv runs top to bottom.
+    let local_p = (in.uv - 0.5) * vec2<f32>(2.0, -2.0) * params.h_extent;
+    if sdf_shape(local_p, params.shape, params.h_extent, params.radius) > 0.0 {
+        discard;
+    }
+    return vec4<f32>(1.0);
+}
@@ -0,0 +1,80 @@
+#define_import_path bevy_magic_light_2d::gi_occluder_shapes
+
+const OCCLUDER_SHAPE_BOX:         u32 = 0u;
+const OCCLUDER_SHAPE_CIRCLE:      u32 = 1u;
+const OCCLUDER_SHAPE_CAPSULE:     u32 = 2u;
+const OCCLUDER_SHAPE_ROUNDED_BOX: u32 = 3u;
+// Solid fill, the rasterised geometry is the occluder itself.
+const OCCLUDER_SHAPE_FILL:        u32 = 4u;
+
+fn sdf_box(local_p: vec2<f32>, h_extent: vec2<f32>) -> f32 {
+    let d        = abs(local_p) - h_extent;
+    let d_max    = max(d, vec2<f32>(0.0));
+    let d_o      = length(d_max);
+    let d_i      = min(max(d.x, d.y), 0.0);
+    return d_o + d_i;
+}
+
+fn sdf_circle(local_p: vec2<f32>, radius: f32) -> f32 {
+    return length(local_p) - radius;
+}
+
+// Capsule inscribed in the box, its segment runs along the longer axis.
+fn sdf_capsule(local_p: vec2<f32>, h_extent: vec2<f32>) -> f32 {
+    let radius  = min(h_extent.x, h_extent.y);
+    let segment = h_extent - radius;
+    return length(local_p - clamp(local_p, -segment, segment)) - radius;
+}
+
+fn sdf_rounded_box(local_p: vec2<f32>, h_extent: vec2<f32>, radius: f32) -> f32 {
+    return sdf_box(local_p, h_extent - radius) - radius;
+}
+
+// Distance to an occluder shape, `local_p` is relative to its centre.
+fn sdf_shape(local_p: vec2<f32>, shape: u32, h_extent: vec2<f32>, radius: f32) -> f32 {
+    switch shape {
+        case OCCLUDER_SHAPE_CIRCLE: {
+            return sdf_circle(local_p, min(h_extent.x, h_extent.y));
+        }
+        case OCCLUDER_SHAPE_CAPSULE: {
+            return sdf_capsule(local_p, h_extent);
+        }
+        case OCCLUDER_SHAPE_ROUNDED_BOX: {
+            return sdf_rounded_box(local_p, h_extent, radius);
+        }
+        case OCCLUDER_SHAPE_FILL: {
+            return -1.0;
+        }
+        default: {
+            return sdf_box(local_p, h_extent);
+        }
+    }
+}
+
+const OCCLUDER_OP_UNION:        u32 = 0u;
+const OCCLUDER_OP_SMOOTH_UNION: u32 = 1u;
+const OCCLUDER_OP_SUBTRACT:     u32 = 2u;
+
+// Quadratic smooth minimum, blends distances closer than `radius`.
+fn smooth_min(a: f32, b: f32, radius: f32) -> f32 {
+    if radius <= 0.0 {
+        return min(a, b);
+    }
+    let h = max(radius - abs(a - b), 0.0) / radius;
+    return min(a, b) - h * h * radius * 0.25;
+}
+
+// Combines the distance to an occluder into the merged distance `d`.
+fn sdf_combine(d: f32, occluder_d: f32, operation: u32, blend_radius: f32) -> f32 {
+    switch operation {
+        case OCCLUDER_OP_SMOOTH_UNION: {
+            return smooth_min(d, occluder_d, blend_radius);
+        }
+        case OCCLUDER_OP_SUBTRACT: {
+            return -smooth_min(-d, occluder_d, blend_radius);
+        }
+        default: {
+            return min(d, occluder_d);
+        }
+    }
+}
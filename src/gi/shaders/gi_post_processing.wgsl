@@ -13,6 +13,8 @@
 @group(2) @binding(5) var in_objects_sampler:            sampler;
 @group(2) @binding(6) var in_irradiance_texture:         texture_2d<f32>;
 @group(2) @binding(7) var in_irradiance_texture_sampler: sampler;
+@group(2) @binding(8) var in_emissive_texture:           texture_2d<f32>;
+@group(2) @binding(9) var in_emissive_sampler:           sampler;
 
 fn lin_to_srgb(color: vec3<f32>) -> vec3<f32> {
    let x = color * 12.92;
@@ -33,6 +35,7 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     let in_floor_diffuse   = textureSample(in_floor_texture,   in_floor_sampler, uv);
     let in_walls_diffuse   = textureSample(in_walls_texture,   in_walls_sampler, uv);
     let in_objects_diffuse = textureSample(in_objects_texture, in_objects_sampler, uv);
+    let in_emissive        = textureSample(in_emissive_texture, in_emissive_sampler, uv);
 
     let in_irradiance = textureSample(in_irradiance_texture, in_irradiance_texture_sampler, uv).xyz;
 
@@ -75,5 +78,8 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
         out = vec4<f32>(mix(out.xyz, final_walls.xyz, in_walls_diffuse.w), 1.0);
         out = vec4<f32>(mix(out.xyz, final_objects.xyz, in_objects_diffuse.w), 1.0);
 
+    // Emissive sprites are not lit, they are added on top.
+        out = vec4<f32>(out.xyz + in_emissive.xyz * in_emissive.w, 1.0);
+
     return out;
 }
@@ -1,12 +1,35 @@
 #define_import_path bevy_magic_light_2d::gi_raymarch
 
 #import bevy_magic_light_2d::gi_math::{fast_normalize_2d, distance_squared, hash}
-#import bevy_magic_light_2d::gi_camera::{CameraParams, sdf_uv_to_world, world_to_sdf_uv, bilinear_sample_r}
+#import bevy_magic_light_2d::gi_camera::{CameraParams, sdf_uv_to_world, world_to_sdf_uv, bilinear_sample_r, bilinear_sample_a, bilinear_sample_rgba}
 
 struct RayMarchResult {
     success:  i32,      //
     step: i32,          // steps
     pose: vec2<f32>,    // curr spot
+    transmittance: vec3<f32>, // light let through by translucent occluders along the ray
+    stop: u32,          // why the march stopped, one of `RAY_*`
+}
+
+const RAY_REACHED:   u32 = 0u; // reached its target
+const RAY_HIT:       u32 = 1u; // stopped on an opaque occluder
+const RAY_ABSORBED:  u32 = 2u; // translucent occluders let no light through
+const RAY_EXITED:    u32 = 3u; // left the SDF
+const RAY_EXHAUSTED: u32 = 4u; // ran out of steps
+
+// Rays whose transmittance drops below this in every channel are occluded.
+const MIN_TRANSMITTANCE: f32 = 1e-3;
+
+// The transmittance map holds in rgb the light let through by the translucent
+// occluders around each texel, and in alpha the distance to opaque occluders
+// only. Multiplies `transmittance` by the occluders entered at `uv`.
+fn enter_translucent(
+    transmittance:     vec3<f32>,
+    transmittance_map: texture_2d<f32>,
+    sdf_sampler:       sampler,
+    uv:                vec2<f32>,
+) -> vec3<f32> {
+    return transmittance * bilinear_sample_rgba(transmittance_map, sdf_sampler, uv).xyz;
 }
 
 fn raymarch(
@@ -47,18 +70,18 @@ fn raymarch(
         h = ray_origin + ray_progress * ray_direction;
 
         if ((ray_progress * ray_progress >= stop_at) || (inside && (ray_progress * ray_progress > max_inside_dist))) {
-            return RayMarchResult(1, i, h_prev);
+            return RayMarchResult(1, i, h_prev, vec3<f32>(1.0), RAY_REACHED);
         }
 
 
         let uv = world_to_sdf_uv(h, camera_params.view_proj, camera_params.inv_sdf_scale);
         if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
-            return RayMarchResult(0, i, h_prev);
+            return RayMarchResult(0, i, h_prev, vec3<f32>(0.0), RAY_EXITED);
         }
 
         let scene_dist = bilinear_sample_r(sdf, sdf_sampler, uv);
         if ((scene_dist <= min_sdf && !inside)) {
-            return RayMarchResult(0, i, h);
+            return RayMarchResult(0, i, h, vec3<f32>(0.0), RAY_HIT);
         }
         if (scene_dist > 0.0) {
             inside = false;
@@ -74,7 +97,7 @@ fn raymarch(
         }
     }
 
-    return RayMarchResult(0, max_steps, h);
+    return RayMarchResult(0, max_steps, h, vec3<f32>(0.0), RAY_EXHAUSTED);
 }
 
 fn raymarch_primary(
@@ -83,6 +106,7 @@ fn raymarch_primary(
     max_steps:          i32,
     sdf:                texture_2d<f32>,
     sdf_sampler:        sampler,
+    transmittance_map:  texture_2d<f32>,
     camera_params:      CameraParams,
     rm_jitter_contrib:  f32,
 ) -> RayMarchResult {
@@ -97,6 +121,8 @@ fn raymarch_primary(
     var h                      = vec2<f32>(0.0);
     var h_prev                 = h;
     let min_sdf                = 1e-4;
+    var transmittance          = vec3<f32>(1.0);
+    var inside                 = false;
 
     for (var i: i32 = 0; i < max_steps; i++) {
 
@@ -104,35 +130,112 @@ fn raymarch_primary(
         h = ray_origin + ray_progress * ray_direction;
 
         if ray_progress * ray_progress >= stop_at {
-            return RayMarchResult(1, i, h_prev);
+            return RayMarchResult(1, i, h_prev, transmittance, RAY_REACHED);
         }
 
 
         let uv = world_to_sdf_uv(h, camera_params.view_proj, camera_params.inv_sdf_scale);
         if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
-            return RayMarchResult(0, i, h_prev);
+            return RayMarchResult(0, i, h_prev, vec3<f32>(0.0), RAY_EXITED);
         }
 
-        let scene_dist = bilinear_sample_r(sdf, sdf_sampler, uv);
-        if scene_dist <= min_sdf {
-            return RayMarchResult(0, i, h);
+        let scene_dist  = bilinear_sample_r(sdf, sdf_sampler, uv);
+        let opaque_dist = bilinear_sample_a(transmittance_map, sdf_sampler, uv);
+        if opaque_dist <= min_sdf {
+            return RayMarchResult(0, i, h, vec3<f32>(0.0), RAY_HIT);
+        }
+
+        // Translucent occluders attenuate the ray once per crossing.
+        if scene_dist <= min_sdf && !inside {
+            transmittance = enter_translucent(transmittance, transmittance_map, sdf_sampler, uv);
+            if all(transmittance < vec3<f32>(MIN_TRANSMITTANCE)) {
+                return RayMarchResult(0, i, h, vec3<f32>(0.0), RAY_ABSORBED);
+            }
         }
+        inside = scene_dist <= min_sdf;
 
-        let ray_travel = max(abs(scene_dist), 0.0);
+        let ray_travel = max(min(abs(scene_dist), opaque_dist), select(0.0, 0.5, inside));
 
         ray_progress += ray_travel * (1.0 - rm_jitter_contrib) + rm_jitter_contrib * ray_travel * hash(h);
    }
 
-    return RayMarchResult(0, max_steps, h);
+    return RayMarchResult(0, max_steps, h, vec3<f32>(0.0), RAY_EXHAUSTED);
 }
 
 
+// Traces a cone from the origin to a spherical emitter of `source_radius` and
+// returns the unoccluded fraction of the emitter, tinted by the translucent
+// occluders crossed. The cone radius at each step is compared to the distance
+// to opaque occluders, giving a penumbra that widens away from the occluder.
+fn raymarch_soft_shadow(
+    in_ray_origin:      vec2<f32>,
+    in_ray_target:      vec2<f32>,
+    source_radius:      f32,
+    max_steps:          i32,
+    sdf:                texture_2d<f32>,
+    sdf_sampler:        sampler,
+    transmittance_map:  texture_2d<f32>,
+    camera_params:      CameraParams,
+    rm_jitter_contrib:  f32,
+) -> vec3<f32> {
+
+    let ray_length = distance(in_ray_origin, in_ray_target);
+    if ray_length <= 0.0 {
+        return vec3<f32>(1.0);
+    }
+
+    let ray_direction          = (in_ray_target - in_ray_origin) / ray_length;
+    let min_sdf                = 1e-4;
+
+    var ray_progress:   f32    = 0.0;
+    var visibility:     f32    = 1.0;
+    var transmittance          = vec3<f32>(1.0);
+    var inside                 = false;
+
+    for (var i: i32 = 0; i < max_steps; i++) {
+
+        if ray_progress >= ray_length {
+            return transmittance * visibility;
+        }
+
+        let h  = in_ray_origin + ray_progress * ray_direction;
+        let uv = world_to_sdf_uv(h, camera_params.view_proj, camera_params.inv_sdf_scale);
+        if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+            return vec3<f32>(0.0);
+        }
+
+        let scene_dist  = bilinear_sample_r(sdf, sdf_sampler, uv);
+        let opaque_dist = bilinear_sample_a(transmittance_map, sdf_sampler, uv);
+        let cone_radius = max(source_radius * ray_progress / ray_length, 1e-4);
+
+        visibility = min(visibility, clamp(0.5 + 0.5 * opaque_dist / cone_radius, 0.0, 1.0));
+        if visibility <= 0.0 {
+            return vec3<f32>(0.0);
+        }
+
+        if scene_dist <= min_sdf && !inside {
+            transmittance = enter_translucent(transmittance, transmittance_map, sdf_sampler, uv);
+            if all(transmittance < vec3<f32>(MIN_TRANSMITTANCE)) {
+                return vec3<f32>(0.0);
+            }
+        }
+        inside = scene_dist <= min_sdf;
+
+        let ray_travel = max(min(abs(scene_dist), abs(opaque_dist)), 0.5);
+
+        ray_progress += ray_travel * (1.0 - rm_jitter_contrib) + rm_jitter_contrib * ray_travel * hash(h);
+    }
+
+    return vec3<f32>(0.0);
+}
+
 fn raymarch_bounce(
     in_ray_origin:      vec2<f32>,
     in_ray_target:      vec2<f32>,
     max_steps:          i32,
     sdf:                texture_2d<f32>,
     sdf_sampler:        sampler,
+    transmittance_map:  texture_2d<f32>,
     camera_params:      CameraParams,
     rm_jitter_contrib:  f32,
 ) -> RayMarchResult {
@@ -156,6 +259,8 @@ fn raymarch_bounce(
     var h                      = vec2<f32>(0.0);
     var h_prev                 = h;
     let min_sdf                = 1e-4;
+    var transmittance          = vec3<f32>(1.0);
+    var inside                 = false;
 
     for (var i: i32 = 0; i < max_steps; i++) {
 
@@ -163,24 +268,93 @@ fn raymarch_bounce(
         h = ray_origin + ray_progress * ray_direction;
 
         if ray_progress * ray_progress >= stop_at {
-            return RayMarchResult(1, i, h_prev);
+            return RayMarchResult(1, i, h_prev, transmittance, RAY_REACHED);
         }
 
         let uv = world_to_sdf_uv(h, camera_params.view_proj, camera_params.inv_sdf_scale);
         if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
-            return RayMarchResult(0, i, h_prev);
+            return RayMarchResult(0, i, h_prev, vec3<f32>(0.0), RAY_EXITED);
         }
 
-        let scene_dist = bilinear_sample_r(sdf, sdf_sampler, uv);
-        if  scene_dist <= min_sdf {
-            return RayMarchResult(0, i, h);
+        let scene_dist  = bilinear_sample_r(sdf, sdf_sampler, uv);
+        let opaque_dist = bilinear_sample_a(transmittance_map, sdf_sampler, uv);
+        // Keep the light let through up to the hit, for light reflected by
+        // the occluder.
+        if opaque_dist <= min_sdf {
+            return RayMarchResult(0, i, h, transmittance, RAY_HIT);
         }
 
-        let ray_travel = max(abs(scene_dist), 0.5);
+        if scene_dist <= min_sdf && !inside {
+            transmittance = enter_translucent(transmittance, transmittance_map, sdf_sampler, uv);
+            if all(transmittance < vec3<f32>(MIN_TRANSMITTANCE)) {
+                return RayMarchResult(0, i, h, vec3<f32>(0.0), RAY_ABSORBED);
+            }
+        }
+        inside = scene_dist <= min_sdf;
+
+        let ray_travel = max(min(abs(scene_dist), opaque_dist), 0.5);
 
         ray_progress += ray_travel * (1.0 - rm_jitter_contrib)
                       + rm_jitter_contrib * ray_travel * hash(h);
     }
 
-    return RayMarchResult(0, max_steps, h);
-}
\ No newline at end of file
+    return RayMarchResult(0, max_steps, h, vec3<f32>(0.0), RAY_EXHAUSTED);
+}
+
+// Marches along a direction for a fixed distance. Unlike `raymarch_primary`,
+// leaving the SDF texture counts as success: distant lights are not occluded
+// by geometry that is outside of the SDF.
+fn raymarch_directional(
+    in_ray_origin:      vec2<f32>,
+    in_ray_direction:   vec2<f32>,
+    max_distance:       f32,
+    max_steps:          i32,
+    sdf:                texture_2d<f32>,
+    sdf_sampler:        sampler,
+    transmittance_map:  texture_2d<f32>,
+    camera_params:      CameraParams,
+    rm_jitter_contrib:  f32,
+) -> RayMarchResult {
+
+    var ray_progress:   f32    = 0.0;
+    var h                      = in_ray_origin;
+    var h_prev                 = h;
+    let min_sdf                = 1e-4;
+    var transmittance          = vec3<f32>(1.0);
+    var inside                 = false;
+
+    for (var i: i32 = 0; i < max_steps; i++) {
+
+        h_prev = h;
+        h = in_ray_origin + ray_progress * in_ray_direction;
+
+        if ray_progress >= max_distance {
+            return RayMarchResult(1, i, h_prev, transmittance, RAY_REACHED);
+        }
+
+        let uv = world_to_sdf_uv(h, camera_params.view_proj, camera_params.inv_sdf_scale);
+        if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+            return RayMarchResult(1, i, h_prev, transmittance, RAY_EXITED);
+        }
+
+        let scene_dist  = bilinear_sample_r(sdf, sdf_sampler, uv);
+        let opaque_dist = bilinear_sample_a(transmittance_map, sdf_sampler, uv);
+        if opaque_dist <= min_sdf {
+            return RayMarchResult(0, i, h, vec3<f32>(0.0), RAY_HIT);
+        }
+
+        if scene_dist <= min_sdf && !inside {
+            transmittance = enter_translucent(transmittance, transmittance_map, sdf_sampler, uv);
+            if all(transmittance < vec3<f32>(MIN_TRANSMITTANCE)) {
+                return RayMarchResult(0, i, h, vec3<f32>(0.0), RAY_ABSORBED);
+            }
+        }
+        inside = scene_dist <= min_sdf;
+
+        let ray_travel = max(min(abs(scene_dist), opaque_dist), 0.5);
+
+        ray_progress += ray_travel * (1.0 - rm_jitter_contrib) + rm_jitter_contrib * ray_travel * hash(h);
+    }
+
+    return RayMarchResult(0, max_steps, h, vec3<f32>(0.0), RAY_EXHAUSTED);
+}
@@ -1,28 +1,92 @@
-#import bevy_magic_light_2d::gi_types::LightOccluderBuffer
+#import bevy_magic_light_2d::gi_types::{
+    LightOccluder,
+    LightOccluderBuffer,
+    PolygonOccluder,
+    PolygonOccluderBuffer,
+    OccluderVertexBuffer,
+    OccluderTileBuffer,
+    StaticSdfParams,
+    OCCLUDER_TILE_SIZE,
+    pack_material,
+    material_albedo,
+    material_emissive,
+}
 #import bevy_magic_light_2d::gi_math::quat_mul
 #import bevy_magic_light_2d::gi_camera::{CameraParams, sdf_uv_to_world}
+#import bevy_magic_light_2d::gi_occluder_shapes::{
+    sdf_box,
+    sdf_shape,
+    sdf_combine,
+    OCCLUDER_OP_SUBTRACT,
+}
 
 @group(0) @binding(0) var<uniform> camera_params:         CameraParams;
 @group(0) @binding(1) var<storage> light_occluder_buffer: LightOccluderBuffer;
 @group(0) @binding(2) var          sdf_out:               texture_storage_2d<r16float, read_write>;
+@group(0) @binding(3) var<storage> polygon_occluder_buffer: PolygonOccluderBuffer;
+@group(0) @binding(4) var<storage> occluder_vertex_buffer:  OccluderVertexBuffer;
+@group(0) @binding(5) var<storage> occluder_tile_buffer:    OccluderTileBuffer;
+@group(0) @binding(10) var         material_out:            texture_storage_2d<rgba32uint, write>;
+
+// The static SDF cache is built by this shader with `STATIC_SDF` defined, from
+// the static occluders only, and merged into the SDF otherwise.
+#ifndef STATIC_SDF
+@group(0) @binding(6) var          static_sdf:              texture_2d<f32>;
+@group(0) @binding(7) var          static_sdf_sampler:      sampler;
+@group(0) @binding(8) var<uniform> static_sdf_params:       StaticSdfParams;
+@group(0) @binding(9) var          transmittance_out:       texture_storage_2d<rgba16float, write>;
+@group(0) @binding(11) var         static_material:         texture_2d<u32>;
+#endif
+
+// Translucent occluders tint the texels closer than this many texels, so that
+// rays entering them sample their transmission despite filtering.
+const TRANSMISSION_RIM_TEXELS: f32 = 2.0;
+
+fn sdf_occluder(p: vec2<f32>, occluder: LightOccluder) -> f32 {
+    let local_p  = quat_mul(occluder.rotation, vec3<f32>(occluder.center - p, 0.0)).xy;
+    return sdf_shape(local_p, occluder.shape, occluder.h_extent, occluder.radius);
+}
+
+// Exact distance to the polygon edges, signed with the winding number so that
+// concave and self-overlapping polygons are handled. The winding number sums
+// over all contours, holes wind the other way.
+fn sdf_polygon(p: vec2<f32>, polygon_i: i32) -> f32 {
+    let contour_end = polygon_i + i32(polygon_occluder_buffer.data[polygon_i].contour_count);
+
+    var d_sq    = 1e+20;
+    var winding = 0;
+    for (var c: i32 = polygon_i; c < contour_end; c++) {
+        let contour = polygon_occluder_buffer.data[c];
+        for (var k: u32 = 0u; k < contour.vertex_count; k++) {
+            let a = occluder_vertex_buffer.data[contour.vertex_offset + k];
+            let b = occluder_vertex_buffer.data[contour.vertex_offset + (k + 1u) % contour.vertex_count];
+            let e = b - a;
+            let w = p - a;
+
+            let t = clamp(dot(w, e) / max(dot(e, e), 1e-8), 0.0, 1.0);
+            let q = w - e * t;
+            d_sq  = min(d_sq, dot(q, q));
+
+            // Signed area of (a, b, p), positive when p is left of the edge.
+            let side = e.x * w.y - e.y * w.x;
+            if a.y <= p.y {
+                if b.y > p.y && side > 0.0 {
+                    winding += 1;
+                }
+            } else if b.y <= p.y && side < 0.0 {
+                winding -= 1;
+            }
+        }
+    }
 
-fn sdf_aabb_occluder(p: vec2<f32>, occluder_i: i32) -> f32 {
-    let occluder = light_occluder_buffer.data[occluder_i];
-    let local_p = quat_mul(occluder.rotation, vec3<f32>(occluder.center - p, 0.0)).xy;
-    let d        = abs(local_p) - occluder.h_extent;
-    let d_max    = max(d, vec2<f32>(0.0));
-    let d_o      = length(d_max);
-    let d_i      = min(max(d.x, d.y), 0.0);
-    return d_o + d_i;
+    let d = sqrt(d_sq);
+    return select(d, -d, winding != 0);
 }
 
-fn round_merge(s1: f32, s2: f32, r: f32) -> f32 {
-    var intersection_space = vec2<f32>(s1 - r, s1 - r); // s1, s1 is intended
-        intersection_space = min(intersection_space, vec2<f32>(0.0));
-    let inside_distance    = -length(intersection_space);
-    let simple_union       = min(s1, s2);
-    let outside_distance   = max(simple_union, r);
-    return inside_distance + outside_distance;
+// Polygons are never closer than their bounds.
+fn sdf_polygon_bounds(p: vec2<f32>, polygon: PolygonOccluder) -> f32 {
+    let h_bounds = (polygon.bounds_max - polygon.bounds_min) * 0.5;
+    return sdf_box(p - polygon.bounds_min - h_bounds, h_bounds);
 }
 
 @compute @workgroup_size(8, 8, 1)
@@ -34,16 +98,154 @@ fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
      let world_pose = sdf_uv_to_world(uv,
         camera_params.inverse_view_proj,
         camera_params.sdf_scale);
-    let r = 1.2;
 
-     var sdf_merged   = round_merge(
-        1e+10,
-        sdf_aabb_occluder(world_pose.xy, 0),
-        r,
-     );
-     for (var i: i32 = 1; i < i32(light_occluder_buffer.count); i++) {
-        sdf_merged = round_merge(sdf_merged, sdf_aabb_occluder(world_pose.xy, i), r);
-     }
+    let texel_size = max(
+        distance(sdf_uv_to_world(uv + vec2<f32>(1.0 / f32(dims.x), 0.0),
+            camera_params.inverse_view_proj,
+            camera_params.sdf_scale), world_pose.xy),
+        distance(sdf_uv_to_world(uv + vec2<f32>(0.0, 1.0 / f32(dims.y)),
+            camera_params.inverse_view_proj,
+            camera_params.sdf_scale), world_pose.xy),
+    );
+    let rim = texel_size * TRANSMISSION_RIM_TEXELS;
+
+    // Only occluders binned into this tile can be closer than the clamp.
+    var sdf_merged = occluder_tile_buffer.max_distance;
+
+    // Distance to the opaque occluders only, and light let through by the
+    // translucent occluders around the texel.
+    var sdf_opaque    = occluder_tile_buffer.max_distance;
+    var transmittance = vec3<f32>(1.0);
+
+    // Material of the closest occluder, picked up by bounced light.
+    var sdf_nearest   = occluder_tile_buffer.max_distance;
+    var albedo        = vec3<f32>(1.0);
+    var emissive      = vec3<f32>(0.0);
+    var tile_start = 0u;
+    var tile_end   = 0u;
+    let tile       = vec2<u32>(texel_pos) / OCCLUDER_TILE_SIZE;
+    if all(tile < occluder_tile_buffer.tile_count) {
+        let tile_i = tile.y * occluder_tile_buffer.tile_count.x + tile.x;
+        tile_start = occluder_tile_buffer.data[tile_i];
+        tile_end   = occluder_tile_buffer.data[tile_i + 1u];
+    }
+
+    // Unions first, subtractive occluders then carve everything merged. Tiles
+    // list polygons after the light occluders, by their first entry.
+    for (var k: u32 = tile_start; k < tile_end; k++) {
+        let index = occluder_tile_buffer.data[k];
+        if index >= light_occluder_buffer.count {
+            continue;
+        }
+        let occluder = light_occluder_buffer.data[index];
+        if occluder.operation != OCCLUDER_OP_SUBTRACT {
+            let d      = sdf_occluder(world_pose.xy, occluder);
+            sdf_merged = sdf_combine(sdf_merged, d, occluder.operation, occluder.blend_radius);
+            if d < sdf_nearest {
+                sdf_nearest = d;
+                albedo      = unpack4x8unorm(occluder.albedo).xyz;
+                emissive    = occluder.emissive;
+            }
+            if occluder.transmission == 0u {
+                sdf_opaque = sdf_combine(sdf_opaque, d, occluder.operation, occluder.blend_radius);
+            } else if d < rim {
+                transmittance *= unpack4x8unorm(occluder.transmission).xyz;
+            }
+        }
+    }
+
+#ifndef STATIC_SDF
+    if static_sdf_params.enabled != 0u {
+        let static_size = static_sdf_params.bounds_max - static_sdf_params.bounds_min;
+        let static_uv   = (world_pose.xy - static_sdf_params.bounds_min) / static_size;
+        if all(static_uv >= vec2<f32>(0.0)) && all(static_uv <= vec2<f32>(1.0)) {
+            let static_sample_uv = vec2<f32>(static_uv.x, 1.0 - static_uv.y);
+            let static_sdf_value = textureSampleLevel(
+                static_sdf,
+                static_sdf_sampler,
+                static_sample_uv,
+                0.0,
+            ).r;
+            sdf_merged = min(sdf_merged, static_sdf_value);
+            sdf_opaque = min(sdf_opaque, static_sdf_value);
+            if static_sdf_value < sdf_nearest {
+                let static_dims     = textureDimensions(static_material);
+                let static_texel    = min(
+                    vec2<u32>(static_sample_uv * vec2<f32>(static_dims)),
+                    static_dims - 1u,
+                );
+                let static_texel_material = textureLoad(static_material, static_texel, 0);
+                sdf_nearest = static_sdf_value;
+                albedo      = material_albedo(static_texel_material);
+                emissive    = material_emissive(static_texel_material);
+            }
+        }
+    }
+#endif
+
+    for (var k: u32 = tile_start; k < tile_end; k++) {
+        let index = occluder_tile_buffer.data[k];
+        if index < light_occluder_buffer.count {
+            continue;
+        }
+        // Skip polygons that can't change the distances or the transmittance.
+        let i       = i32(index - light_occluder_buffer.count);
+        let polygon = polygon_occluder_buffer.data[i];
+        let opaque  = polygon.transmission == 0u;
+        let cull    = select(max(sdf_merged, rim), sdf_opaque, opaque);
+        if polygon.operation == OCCLUDER_OP_SUBTRACT
+            || sdf_polygon_bounds(world_pose.xy, polygon) - polygon.blend_radius >= cull {
+            continue;
+        }
+        let d      = sdf_polygon(world_pose.xy, i);
+        sdf_merged = sdf_combine(sdf_merged, d, polygon.operation, polygon.blend_radius);
+        if d < sdf_nearest {
+            sdf_nearest = d;
+            albedo      = unpack4x8unorm(polygon.albedo).xyz;
+            emissive    = polygon.emissive;
+        }
+        if opaque {
+            sdf_opaque = sdf_combine(sdf_opaque, d, polygon.operation, polygon.blend_radius);
+        } else if d < rim {
+            transmittance *= unpack4x8unorm(polygon.transmission).xyz;
+        }
+    }
+
+    for (var k: u32 = tile_start; k < tile_end; k++) {
+        let index = occluder_tile_buffer.data[k];
+        if index >= light_occluder_buffer.count {
+            continue;
+        }
+        let occluder = light_occluder_buffer.data[index];
+        if occluder.operation == OCCLUDER_OP_SUBTRACT {
+            let d      = sdf_occluder(world_pose.xy, occluder);
+            sdf_merged = sdf_combine(sdf_merged, d, occluder.operation, occluder.blend_radius);
+            sdf_opaque = sdf_combine(sdf_opaque, d, occluder.operation, occluder.blend_radius);
+        }
+    }
+
+    for (var k: u32 = tile_start; k < tile_end; k++) {
+        let index = occluder_tile_buffer.data[k];
+        if index < light_occluder_buffer.count {
+            continue;
+        }
+        let i       = i32(index - light_occluder_buffer.count);
+        let polygon = polygon_occluder_buffer.data[i];
+        if polygon.operation != OCCLUDER_OP_SUBTRACT
+            || sdf_polygon_bounds(world_pose.xy, polygon) - polygon.blend_radius >= -sdf_merged {
+            continue;
+        }
+        let d      = sdf_polygon(world_pose.xy, i);
+        sdf_merged = sdf_combine(sdf_merged, d, polygon.operation, polygon.blend_radius);
+        sdf_opaque = sdf_combine(sdf_opaque, d, polygon.operation, polygon.blend_radius);
+    }
 
     textureStore(sdf_out, texel_pos, vec4<f32>(sdf_merged, 0.0, 0.0, 0.0));
-}
\ No newline at end of file
+    textureStore(material_out, texel_pos, pack_material(albedo, emissive));
+
+#ifndef STATIC_SDF
+    // Carved out translucent occluders don't tint.
+    transmittance = select(transmittance, vec3<f32>(1.0), sdf_merged >= rim);
+    textureStore(transmittance_out, texel_pos, vec4<f32>(transmittance, sdf_opaque));
+#endif
+}
@@ -0,0 +1,20 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct SpriteOccluderParams {
+    uv_min:          vec2<f32>,
+    uv_max:          vec2<f32>,
+    alpha_threshold: f32,
+}
+
+@group(2) @binding(0) var<uniform> params:       SpriteOccluderParams;
+@group(2) @binding(1) var          mask_texture: texture_2d<f32>;
+@group(2) @binding(2) var          mask_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = mix(params.uv_min, params.uv_max, in.uv);
+    if textureSample(mask_texture, mask_sampler, uv).a < params.alpha_threshold {
+        discard;
+    }
+    return vec4<f32>(1.0);
+}
@@ -1,9 +1,9 @@
-#import bevy_magic_light_2d::gi_types::LightPassParams
+#import bevy_magic_light_2d::gi_types::{LightPassParams, material_albedo, material_emissive}
 #import bevy_magic_light_2d::gi_math::fast_normalize_2d
 #import bevy_magic_light_2d::gi_camera::{CameraParams, world_to_sdf_uv, bilinear_sample_rgba, screen_to_world, world_to_screen, world_to_ndc}
 #import bevy_magic_light_2d::gi_halton::radical_inverse_vdc
 #import bevy_magic_light_2d::gi_attenuation
-#import bevy_magic_light_2d::gi_raymarch::raymarch_bounce
+#import bevy_magic_light_2d::gi_raymarch::{raymarch_bounce, RAY_HIT, RAY_REACHED}
 
 @group(0) @binding(0) var<uniform> camera_params:     CameraParams;
 @group(0) @binding(1) var<uniform> cfg:               LightPassParams;
@@ -11,6 +11,8 @@
 @group(0) @binding(3) var          sdf_in_sampler:    sampler;
 @group(0) @binding(4) var          ss_probe_in:       texture_storage_2d<rgba16float, read>;
 @group(0) @binding(5) var          ss_bounce_out:     texture_storage_2d<rgba32float, write>;
+@group(0) @binding(6) var          transmittance_in:  texture_2d<f32>;
+@group(0) @binding(7) var          material_in:       texture_2d<u32>;
 
 
 @compute @workgroup_size(8, 8, 1)
@@ -92,16 +94,33 @@ fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
                 32,
                 sdf_in,
                 sdf_in_sampler,
+                transmittance_in,
                 camera_params,
                 0.3
             );
 
-            if raymarch_sample_to_probe.success <= 0 {
+            // Rays hitting an occluder gather the light emitted by its surface
+            // and the light reaching it, reflected with the albedo of the
+            // occluder.
+            var sample_pose = raymarch_sample_to_probe.pose;
+            var albedo      = vec3<f32>(1.0);
+            if raymarch_sample_to_probe.stop == RAY_HIT {
+                let hit_uv    = world_to_sdf_uv(sample_pose, camera_params.view_proj, camera_params.inv_sdf_scale);
+                let dims      = textureDimensions(material_in);
+                let hit_texel = min(vec2<u32>(hit_uv * vec2<f32>(dims)), dims - 1u);
+                let material  = textureLoad(material_in, hit_texel, 0);
+
+                albedo               = material_albedo(material);
+                indirect_irradiance += material_emissive(material) * raymarch_sample_to_probe.transmittance;
+                sample_pose          = sample_pose + fast_normalize_2d(probe_center_world - sample_pose) * probe_size_f32;
+            } else if raymarch_sample_to_probe.stop != RAY_REACHED {
+                // Rays leaving the SDF, running out of steps or fully absorbed
+                // hit nothing.
                 continue;
             }
 
             let sample_screen = world_to_screen(
-                raymarch_sample_to_probe.pose,
+                sample_pose,
                 camera_params.screen_size,
                 camera_params.view_proj);
 
@@ -123,7 +142,8 @@ fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
                 continue;
             }
 
-            let sample_irradiance = sample_xyz;
+            // Light bounced through translucent occluders is tinted by them.
+            let sample_irradiance = sample_xyz * raymarch_sample_to_probe.transmittance * albedo;
             indirect_irradiance  += sample_irradiance * 0.6; // 0.4 is absorbed by surface.
         }
     }
@@ -13,6 +13,7 @@
 @group(0) @binding(5) var          ss_blend_in:       texture_storage_2d<rgba32float, read>;
 @group(0) @binding(6) var          ss_filter_out:     texture_storage_2d<rgba32float, write>;
 @group(0) @binding(7) var          ss_pose_out:      texture_storage_2d<rg32float, write>;
+@group(0) @binding(8) var          transmittance_in:  texture_2d<f32>;
 
 fn gauss(x: f32) -> f32 {
     let a = 4.0;
@@ -80,6 +81,7 @@ fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
                 8,
                 sdf_in,
                 sdf_in_sampler,
+                transmittance_in,
                 camera_params,
                 0.0).success <= 0 {
                 continue;
@@ -1,9 +1,10 @@
-#import bevy_magic_light_2d::gi_types::{LightPassParams, ProbeDataBuffer, SkylightMaskBuffer, LightSourceBuffer}
+#import bevy_magic_light_2d::gi_types::{LightCookie, LightPassParams, ProbeDataBuffer, SkylightMask, SkylightMaskBuffer, OccluderVertexBuffer, SKYLIGHT_MASK_CIRCLE, SKYLIGHT_MASK_POLYGON, LightSourceBuffer, SpotLightSourceBuffer, AreaLightSourceBuffer, DirectionalLightBuffer, LIGHT_TILE_STRIDE, LIGHT_KIND_SHIFT, LIGHT_INDEX_MASK, LIGHT_KIND_OMNI, LIGHT_KIND_SPOT}
 #import bevy_magic_light_2d::gi_math
-#import bevy_magic_light_2d::gi_camera::{CameraParams, world_to_sdf_uv, bilinear_sample_rgba, screen_to_world, world_to_ndc, ndc_to_screen, bilinear_sample_r}
-#import bevy_magic_light_2d::gi_attenuation::light_attenuation_r_two
+#import bevy_magic_light_2d::gi_occluder_shapes::{sdf_box, sdf_circle}
+#import bevy_magic_light_2d::gi_camera::{CameraParams, world_to_sdf_uv, bilinear_sample_rgba, screen_to_world, world_to_ndc, ndc_to_screen, bilinear_sample_a}
+#import bevy_magic_light_2d::gi_attenuation::{light_attenuation, spot_cone_attenuation}
 #import bevy_magic_light_2d::gi_halton::hammersley2d
-#import bevy_magic_light_2d::gi_raymarch::{raymarch_bounce, raymarch_primary}
+#import bevy_magic_light_2d::gi_raymarch::{raymarch_bounce, raymarch_primary, raymarch_directional, raymarch_soft_shadow}
 
 @group(0) @binding(0) var<uniform> camera_params:         CameraParams;
 @group(0) @binding(1) var<uniform> cfg:                   LightPassParams;
@@ -13,10 +14,228 @@
 @group(0) @binding(5) var          sdf_in:                texture_2d<f32>;
 @group(0) @binding(6) var          sdf_in_sampler:        sampler;
 @group(0) @binding(7) var          ss_probe_out:          texture_storage_2d<rgba16float, write>;
+@group(0) @binding(8) var<storage> spot_lights_buffer:    SpotLightSourceBuffer;
+@group(0) @binding(9) var<storage> area_lights_buffer:    AreaLightSourceBuffer;
+@group(0) @binding(10) var<storage> directional_lights_buffer: DirectionalLightBuffer;
+@group(0) @binding(11) var          emissive_in:               texture_2d<f32>;
+@group(0) @binding(12) var          emissive_in_sampler:       sampler;
+@group(0) @binding(13) var          light_cookies:             texture_2d_array<f32>;
+@group(0) @binding(14) var          light_cookies_sampler:     sampler;
+@group(0) @binding(15) var<storage> light_tiles:               array<u32>;
+@group(0) @binding(16) var          transmittance_in:          texture_2d<f32>;
+@group(0) @binding(17) var<storage> skylight_vertices:         OccluderVertexBuffer;
 
+// Signed distance to a skylight mask, negative inside.
+fn sdf_skylight_mask(p: vec2<f32>, mask: SkylightMask) -> f32 {
+    if mask.shape != SKYLIGHT_MASK_POLYGON {
+        let local_p = gi_math::quat_mul(mask.rotation, vec3<f32>(p - mask.center, 0.0)).xy;
+        if mask.shape == SKYLIGHT_MASK_CIRCLE {
+            return sdf_circle(local_p, min(mask.h_extent.x, mask.h_extent.y));
+        }
+        return sdf_box(local_p, mask.h_extent);
+    }
+
+    // Polygons are signed with the even-odd rule.
+    var d_sq   = 1e+20;
+    var inside = false;
+    for (var k: u32 = 0u; k < mask.vertex_count; k++) {
+        let a = skylight_vertices.data[mask.vertex_offset + k];
+        let b = skylight_vertices.data[mask.vertex_offset + (k + 1u) % mask.vertex_count];
+        let e = b - a;
+        let w = p - a;
+        let q = w - e * clamp(dot(w, e) / max(dot(e, e), 1e-8), 0.0, 1.0);
+        d_sq  = min(d_sq, dot(q, q));
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + e.x * (p.y - a.y) / e.y {
+            inside = !inside;
+        }
+    }
+    let d = sqrt(d_sq);
+    return select(d, -d, inside);
+}
+
+// Number of shadow rays traced towards each line or area light per frame.
+const AREA_LIGHT_SAMPLES: i32 = 4;
+
+// Taps along each axis of the probe averaging the emissive target.
+const EMISSIVE_TAPS: i32 = 4;
+
+const LIGHT_COOKIE_PLANAR: u32 = 1u;
+
+// Fraction of a point emitter visible from the sample, tinted by the
+// translucent occluders in between. Lights that don't cast shadows are always
+// visible, a zero source radius gives hard shadows.
+fn light_visibility(
+    sample_pose:   vec2<f32>,
+    light_pose:    vec2<f32>,
+    cast_shadows:  u32,
+    source_radius: f32,
+) -> vec3<f32> {
+    if cast_shadows == 0u {
+        return vec3<f32>(1.0);
+    }
+
+    if source_radius <= 0.0 {
+        let ray_result = raymarch_primary(
+            sample_pose,
+            light_pose,
+            32,
+            sdf_in,
+            sdf_in_sampler,
+            transmittance_in,
+            camera_params,
+            0.3
+        );
+        return ray_result.transmittance * f32(ray_result.success > 0);
+    }
+
+    return raymarch_soft_shadow(
+        sample_pose,
+        light_pose,
+        source_radius,
+        32,
+        sdf_in,
+        sdf_in_sampler,
+        transmittance_in,
+        camera_params,
+        0.3
+    );
+}
+
+fn light_cookie_color(cookie: LightCookie, light_pose: vec2<f32>, sample_pose: vec2<f32>) -> vec3<f32> {
+    if cookie.index < 0 {
+        return vec3<f32>(1.0);
+    }
+
+    // Position of the sample in the light local space.
+    let d       = sample_pose - light_pose;
+    let axis_y  = vec2<f32>(-cookie.axis.y, cookie.axis.x);
+    let local_p = vec2<f32>(dot(d, cookie.axis), dot(d, axis_y));
+
+    var uv = vec2<f32>(0.0);
+    if cookie.projection == LIGHT_COOKIE_PLANAR {
+        uv = local_p / (2.0 * cookie.size) + 0.5;
+        if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+            return vec3<f32>(0.0);
+        }
+        uv.y = 1.0 - uv.y;
+    } else {
+        let angle = atan2(local_p.y, local_p.x);
+        uv = vec2<f32>(
+            fract(angle / radians(360.0)),
+            clamp(length(local_p) / cookie.size, 0.0, 1.0),
+        );
+    }
+
+    return textureSampleLevel(light_cookies, light_cookies_sampler, uv, cookie.index, 0.0).xyz;
+}
+
+fn omni_light_irradiance(index: u32, probe_center_world: vec2<f32>) -> vec3<f32> {
+    let light = lights_source_buffer.data[index];
+
+    let att = light_attenuation(probe_center_world, light.center, light.falloff);
+    if att <= 0.0 {
+        return vec3<f32>(0.0);
+    }
+
+    let visibility = light_visibility(
+        probe_center_world,
+        light.center,
+        light.cast_shadows,
+        light.source_radius,
+    );
+    if all(visibility <= vec3<f32>(0.0)) {
+        return vec3<f32>(0.0);
+    }
+
+    let cookie = light_cookie_color(light.cookie, light.center, probe_center_world);
+    return light.color * cookie * att * visibility * light.intensity;
+}
+
+fn spot_light_irradiance(index: u32, probe_center_world: vec2<f32>) -> vec3<f32> {
+    let light = spot_lights_buffer.data[index];
+
+    let cone = spot_cone_attenuation(
+        probe_center_world,
+        light.center,
+        light.direction,
+        light.cos_inner,
+        light.cos_outer,
+    );
+
+    let att = light_attenuation(probe_center_world, light.center, light.falloff);
+    if cone <= 0.0 || att <= 0.0 {
+        return vec3<f32>(0.0);
+    }
+
+    let visibility = light_visibility(
+        probe_center_world,
+        light.center,
+        light.cast_shadows,
+        light.source_radius,
+    );
+    if all(visibility <= vec3<f32>(0.0)) {
+        return vec3<f32>(0.0);
+    }
+
+    let cookie = light_cookie_color(light.cookie, light.center, probe_center_world);
+    return light.color * cookie * att * cone * visibility * light.intensity;
+}
+
+// Line and area lights.
+fn area_light_irradiance(index: u32, probe_center_world: vec2<f32>, halton_jitter: vec2<f32>) -> vec3<f32> {
+    let light  = area_lights_buffer.data[index];
+    let axis_x = light.axis;
+    let axis_y = vec2<f32>(-axis_x.y, axis_x.x);
+
+    // Attenuate by distance to the closest point of the emitter.
+    let local_p = probe_center_world - light.center;
+    let closest = light.center
+                + axis_x * clamp(dot(local_p, axis_x), -light.h_extent.x, light.h_extent.x)
+                + axis_y * clamp(dot(local_p, axis_y), -light.h_extent.y, light.h_extent.y);
+
+    let att = light_attenuation(probe_center_world, closest, light.falloff);
+    if att <= 0.0 {
+        return vec3<f32>(0.0);
+    }
+
+    // Estimate visibility of the emitter with stratified samples,
+    // shifted every frame so the reservoir converges to soft shadows.
+    var visibility = vec3<f32>(1.0);
+    if light.cast_shadows != 0u {
+        visibility = vec3<f32>(0.0);
+        for (var s: i32 = 0; s < AREA_LIGHT_SAMPLES; s++) {
+            let u = fract(hammersley2d(s, AREA_LIGHT_SAMPLES) + halton_jitter) * 2.0 - 1.0;
+            let sample_pose = light.center
+                            + axis_x * u.x * light.h_extent.x
+                            + axis_y * u.y * light.h_extent.y;
+
+            let ray_result = raymarch_primary(
+                probe_center_world,
+                sample_pose,
+                32,
+                sdf_in,
+                sdf_in_sampler,
+                transmittance_in,
+                camera_params,
+                0.3
+            );
+
+            if (ray_result.success > 0) {
+                visibility += ray_result.transmittance;
+            }
+        }
+        visibility /= f32(AREA_LIGHT_SAMPLES);
+    }
+
+    return light.color * att * visibility * light.intensity;
+}
 
 @compute @workgroup_size(8, 8, 1)
-fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+fn main(
+    @builtin(global_invocation_id) invocation_id:  vec3<u32>,
+    @builtin(workgroup_id)         workgroup_id:   vec3<u32>,
+    @builtin(num_workgroups)       num_workgroups: vec3<u32>,
+) {
     let tile_xy      = vec2<i32>(invocation_id.xy);
 
     // Screen-space position of the probe.
@@ -41,54 +260,87 @@ fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
     let probe_screen = ndc_to_screen(probe_ndc, camera_params.screen_size);
     var is_masked    = 1.0;
 
-    // Check if the probe is masked from skylight.
+    // Check if the probe is masked from skylight, feathered masks fade it out
+    // inside their edge.
     for (var i: i32 = 0; i < i32(skylight_masks_buffer.count); i++) {
         let mask = skylight_masks_buffer.data[i];
-        if probe_center_world.x > mask.center.x - mask.h_extent.x &&
-           probe_center_world.x < mask.center.x + mask.h_extent.x &&
-           probe_center_world.y > mask.center.y - mask.h_extent.y &&
-           probe_center_world.y < mask.center.y + mask.h_extent.y {
+        let d    = sdf_skylight_mask(probe_center_world, mask);
+        if mask.feather > 0.0 {
+            is_masked = min(is_masked, smoothstep(-mask.feather, 0.0, d));
+        } else if d < 0.0 {
             is_masked = 0.0;
-            break;
         }
     }
 
     var probe_irradiance = vec3<f32>(0.0);
 
     let uv = world_to_sdf_uv(probe_center_world_unbiased, camera_params.view_proj, camera_params.inv_sdf_scale);
-    let dist = bilinear_sample_r( sdf_in, sdf_in_sampler, uv);
+    // Probes inside translucent occluders are still lit.
+    let dist = bilinear_sample_a(transmittance_in, sdf_in_sampler, uv);
     if dist > 0.0 {
 
         let skylight = cfg.skylight_color * is_masked;;
 
-        // Compute direct irradiance from lights in the current frame.
+        // Compute direct irradiance from the lights binned into this tile.
         probe_irradiance = vec3<f32>(skylight);
-        for (var i: i32 = 0; i < i32(lights_source_buffer.count); i++) {
+        let tile_index       = workgroup_id.y * num_workgroups.x + workgroup_id.x;
+        let tile_offset      = tile_index * LIGHT_TILE_STRIDE;
+        let tile_light_count = light_tiles[tile_offset];
+        for (var i: u32 = 0u; i < tile_light_count; i++) {
 
-            let light = lights_source_buffer.data[i];
+            let entry = light_tiles[tile_offset + 1u + i];
+            let index = entry & LIGHT_INDEX_MASK;
 
-            let ray_result = raymarch_primary(
-                probe_center_world,
-                light.center,
-                32,
-                sdf_in,
-                sdf_in_sampler,
-                camera_params,
-                0.3
-            );
+            switch entry >> LIGHT_KIND_SHIFT {
+                case LIGHT_KIND_OMNI: {
+                    probe_irradiance += omni_light_irradiance(index, probe_center_world);
+                }
+                case LIGHT_KIND_SPOT: {
+                    probe_irradiance += spot_light_irradiance(index, probe_center_world);
+                }
+                default: {
+                    probe_irradiance += area_light_irradiance(index, probe_center_world, halton_jitter);
+                }
+            }
+        }
 
-            let att = light_attenuation_r_two(
-                probe_center_world,
-                light.center,
-                light.falloff.x,
-                light.falloff.y,
-                light.falloff.z,
-            );
+        // Compute direct irradiance from directional lights, masked like skylight.
+        for (var i: i32 = 0; i < i32(directional_lights_buffer.count); i++) {
 
-            if (ray_result.success > 0) {
-                probe_irradiance += light.color * att * light.intensity;
+            let light = directional_lights_buffer.data[i];
+
+            var visibility = vec3<f32>(1.0);
+            if light.cast_shadows != 0u {
+                let ray_result = raymarch_directional(
+                    probe_center_world,
+                    -light.direction,
+                    light.shadow_length,
+                    48,
+                    sdf_in,
+                    sdf_in_sampler,
+                    transmittance_in,
+                    camera_params,
+                    0.3
+                );
+                visibility = ray_result.transmittance * f32(ray_result.success > 0);
             }
+
+            probe_irradiance += light.color * light.intensity * visibility * is_masked;
+        }
+
+        // Inject radiance of emissive sprites under the probe, the bounce pass
+        // then spreads it to the surroundings. The emissive target is averaged
+        // over the area covered by the probe jitter, so that sprites smaller
+        // than a probe don't flicker in and out.
+        var emissive = vec3<f32>(0.0);
+        for (var s: i32 = 0; s < EMISSIVE_TAPS * EMISSIVE_TAPS; s++) {
+            let tap       = (vec2<f32>(vec2<i32>(s % EMISSIVE_TAPS, s / EMISSIVE_TAPS)) + 0.5) / f32(EMISSIVE_TAPS);
+            let tap_ndc   = world_to_ndc(probe_center_world_unbiased + tap * probe_size_f32, camera_params.view_proj);
+            let tap_uv    = tap_ndc * vec2<f32>(0.5, -0.5) + 0.5;
+            let tap_color = textureSampleLevel(emissive_in, emissive_in_sampler, tap_uv, 0.0);
+            emissive     += tap_color.xyz * tap_color.w;
         }
+        probe_irradiance += emissive / f32(EMISSIVE_TAPS * EMISSIVE_TAPS) * cfg.emissive_intensity;
 
     }
 
@@ -0,0 +1,15 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(2) @binding(0) var cells: texture_2d<f32>;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The first row of cells is the bottom of the grid, the quad uvs grow
+    // downwards.
+    let dims = vec2<f32>(textureDimensions(cells));
+    let cell = clamp(floor(vec2<f32>(in.uv.x, 1.0 - in.uv.y) * dims), vec2<f32>(0.0), dims - 1.0);
+    if textureLoad(cells, vec2<i32>(cell), 0).r <= 0.5 {
+        discard;
+    }
+    return vec4<f32>(1.0);
+}
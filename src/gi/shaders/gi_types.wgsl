@@ -1,10 +1,25 @@
 #define_import_path bevy_magic_light_2d::gi_types
 
+struct LightCookie {
+    index:      i32,
+    projection: u32,
+    size:       f32,
+    axis:       vec2<f32>,
+}
+
+struct LightFalloff {
+    params: vec4<f32>,
+    model:  u32,
+}
+
 struct LightSource {
-    center:    vec2<f32>,
-    intensity: f32,
-    color:     vec3<f32>,
-    falloff:   vec3<f32>,
+    center:        vec2<f32>,
+    intensity:     f32,
+    color:         vec3<f32>,
+    falloff:       LightFalloff,
+    cast_shadows:  u32,
+    source_radius: f32,
+    cookie:        LightCookie,
 }
 
 struct LightSourceBuffer {
@@ -12,6 +27,52 @@ struct LightSourceBuffer {
     data:  array<LightSource>,
 }
 
+struct SpotLightSource {
+    center:        vec2<f32>,
+    intensity:     f32,
+    color:         vec3<f32>,
+    falloff:       LightFalloff,
+    direction:     vec2<f32>,
+    cos_inner:     f32,
+    cos_outer:     f32,
+    cast_shadows:  u32,
+    source_radius: f32,
+    cookie:        LightCookie,
+}
+
+struct SpotLightSourceBuffer {
+    count: u32,
+    data:  array<SpotLightSource>,
+}
+
+struct AreaLightSource {
+    center:       vec2<f32>,
+    intensity:    f32,
+    color:        vec3<f32>,
+    falloff:      LightFalloff,
+    axis:         vec2<f32>,
+    h_extent:     vec2<f32>,
+    cast_shadows: u32,
+}
+
+struct AreaLightSourceBuffer {
+    count: u32,
+    data:  array<AreaLightSource>,
+}
+
+struct DirectionalLight {
+    color:         vec3<f32>,
+    intensity:     f32,
+    direction:     vec2<f32>,
+    shadow_length: f32,
+    cast_shadows:  u32,
+}
+
+struct DirectionalLightBuffer {
+    count: u32,
+    data:  array<DirectionalLight>,
+}
+
 struct Quaternion {
     data: vec4<f32>,
 }
@@ -20,6 +81,13 @@ struct LightOccluder {
     center: vec2<f32>,
     rotation: Quaternion,
     h_extent: vec2<f32>,
+    shape: u32,
+    radius: f32,
+    operation: u32,
+    blend_radius: f32,
+    transmission: u32,
+    albedo: u32,
+    emissive: vec3<f32>,
 }
 
 struct LightOccluderBuffer {
@@ -27,6 +95,65 @@ struct LightOccluderBuffer {
     data:  array<LightOccluder>,
 }
 
+struct PolygonOccluder {
+    bounds_min:    vec2<f32>,
+    bounds_max:    vec2<f32>,
+    vertex_offset: u32,
+    vertex_count:  u32,
+    operation:     u32,
+    blend_radius:  f32,
+    transmission:  u32,
+    albedo:        u32,
+    contour_count: u32,
+    emissive:      vec3<f32>,
+}
+
+struct PolygonOccluderBuffer {
+    count: u32,
+    data:  array<PolygonOccluder>,
+}
+
+struct OccluderVertexBuffer {
+    count: u32,
+    data:  array<vec2<f32>>,
+}
+
+// Material texels hold the albedo of the closest occluder as `rgba8unorm` in
+// `x`, and its emissive colour as half floats in `y` and `z`.
+fn pack_material(albedo: vec3<f32>, emissive: vec3<f32>) -> vec4<u32> {
+    return vec4<u32>(
+        pack4x8unorm(vec4<f32>(albedo, 1.0)),
+        pack2x16float(emissive.xy),
+        pack2x16float(vec2<f32>(emissive.z, 0.0)),
+        0u,
+    );
+}
+
+fn material_albedo(material: vec4<u32>) -> vec3<f32> {
+    return unpack4x8unorm(material.x).xyz;
+}
+
+fn material_emissive(material: vec4<u32>) -> vec3<f32> {
+    return vec3<f32>(unpack2x16float(material.y), unpack2x16float(material.z).x);
+}
+
+// Occluder tiles cover `OCCLUDER_TILE_SIZE` SDF texels per side, must match
+// the constant in `constants.rs`. `data` starts with the offset of each tile
+// list and the end offset, followed by the lists of occluder indices.
+const OCCLUDER_TILE_SIZE: u32 = 32u;
+
+struct OccluderTileBuffer {
+    tile_count:   vec2<u32>,
+    max_distance: f32,
+    data:         array<u32>,
+}
+
+struct StaticSdfParams {
+    bounds_min: vec2<f32>,
+    bounds_max: vec2<f32>,
+    enabled:    u32,
+}
+
 struct ProbeData {
     pose: vec2<f32>,
 }
@@ -51,14 +178,55 @@ struct LightPassParams {
 
     indirect_rays_per_sample:    i32,
     indirect_rays_radius_factor: f32,
+    emissive_intensity:          f32,
 }
 
+const SKYLIGHT_MASK_BOX:     u32 = 0u;
+const SKYLIGHT_MASK_CIRCLE:  u32 = 1u;
+const SKYLIGHT_MASK_POLYGON: u32 = 2u;
+
 struct SkylightMask {
-    center:   vec2<f32>,
-    h_extent: vec2<f32>,
+    center:        vec2<f32>,
+    rotation:      Quaternion,
+    h_extent:      vec2<f32>,
+    shape:         u32,
+    feather:       f32,
+    vertex_offset: u32,
+    vertex_count:  u32,
 }
 
 struct SkylightMaskBuffer {
     count: u32,
     data:  array<SkylightMask>,
 }
+
+// Light tiles cover a workgroup of probes. Each tile stores its light count
+// followed by up to `LIGHT_TILE_MAX_LIGHTS` entries, must match the constants
+// in `constants.rs`.
+const LIGHT_TILE_MAX_LIGHTS: u32 = 127u;
+const LIGHT_TILE_STRIDE:     u32 = 128u;
+
+// Tile entries pack the light kind in the high bits and its index in the
+// corresponding light buffer in the low bits.
+const LIGHT_KIND_SHIFT: u32 = 30u;
+const LIGHT_INDEX_MASK: u32 = 0x3fffffffu;
+const LIGHT_KIND_OMNI:  u32 = 0u;
+const LIGHT_KIND_SPOT:  u32 = 1u;
+const LIGHT_KIND_AREA:  u32 = 2u;
+
+// Jump flood texels store the nearest occluding texel in `xy` and the nearest
+// empty texel in `zw`, or `JFA_NO_SEED` when none was found yet.
+const JFA_NO_SEED: f32 = -1.0;
+
+struct JfaParams {
+    mask_view_proj: mat4x4<f32>,
+    merge_analytic: u32,
+}
+
+// Distance written where nothing occludes when the SDF is only built by
+// jump flooding.
+const JFA_MAX_DISTANCE: f32 = 1e+4;
+
+struct JfaStepParams {
+    step: i32,
+}
@@ -0,0 +1,298 @@
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::render::render_resource::{AsBindGroup, ShaderRef, ShaderType};
+use bevy::render::view::RenderLayers;
+use bevy::sprite::Material2d;
+
+use crate::gi::compositing::CameraTargets;
+use crate::gi::constants::{OCCLUDER_PROXY_RECT, SDF_VIEW_SCALE};
+use crate::gi::render_layer;
+use crate::gi::types::{LightOccluder2D, OccluderFromSprite, SpriteOccluder2D};
+use crate::{FloorCamera, OccluderCamera};
+
+/// Quad rendering the mask of a [`SpriteOccluder2D`] on the occluder layer.
+#[derive(Component)]
+pub struct SpriteOccluderProxy(Entity);
+
+#[rustfmt::skip]
+#[derive(ShaderType, Clone, Copy, Default, PartialEq)]
+pub struct SpriteOccluderParams {
+    pub uv_min:          Vec2,
+    pub uv_max:          Vec2,
+    pub alpha_threshold: f32,
+}
+
+#[rustfmt::skip]
+#[derive(AsBindGroup, Clone, TypePath, Asset, Default)]
+pub struct SpriteOccluderMaterial {
+    #[uniform(0)]
+    params: SpriteOccluderParams,
+
+    #[texture(1)]
+    #[sampler(2)]
+    mask:   Handle<Image>,
+}
+
+impl Material2d for SpriteOccluderMaterial
+{
+    fn fragment_shader() -> ShaderRef
+    {
+        "embedded://bevy_magic_light_2d/gi/shaders/gi_sprite_occluder.wgsl".into()
+    }
+}
+
+/// Rect of the sprite in its image, from its atlas and rect.
+fn sprite_rect(
+    sprite: Option<&Sprite>,
+    image_size: Vec2,
+    atlas_layouts: &Assets<TextureAtlasLayout>,
+) -> Rect
+{
+    let mut rect = sprite
+        .and_then(|sprite| sprite.texture_atlas.as_ref())
+        .and_then(|atlas| atlas.texture_rect(atlas_layouts))
+        .map_or(Rect::from_corners(Vec2::ZERO, image_size), |rect| {
+            rect.as_rect()
+        });
+    if let Some(sprite_rect) = sprite.and_then(|sprite| sprite.rect) {
+        rect = Rect::from_corners(rect.min + sprite_rect.min, rect.min + sprite_rect.max);
+    }
+    rect
+}
+
+impl SpriteOccluderMaterial
+{
+    /// Resolves the mask material and the quad transform covering the sprite,
+    /// `None` until the mask image is loaded.
+    fn resolve(
+        occluder: &SpriteOccluder2D,
+        sprite: Option<&Sprite>,
+        images: &Assets<Image>,
+        atlas_layouts: &Assets<TextureAtlasLayout>,
+    ) -> Option<(Self, Transform)>
+    {
+        let mask = occluder
+            .mask
+            .as_ref()
+            .or(sprite.map(|sprite| &sprite.image))?;
+        let image_size = images.get(mask)?.size_f32();
+        let rect = sprite_rect(sprite, image_size, atlas_layouts);
+
+        let size = sprite
+            .and_then(|sprite| sprite.custom_size)
+            .unwrap_or(rect.size());
+        let anchor = sprite.map_or(Vec2::ZERO, |sprite| sprite.anchor.as_vec());
+
+        let mut uv_min = rect.min / image_size;
+        let mut uv_max = rect.max / image_size;
+        if sprite.is_some_and(|sprite| sprite.flip_x) {
+            std::mem::swap(&mut uv_min.x, &mut uv_max.x);
+        }
+        if sprite.is_some_and(|sprite| sprite.flip_y) {
+            std::mem::swap(&mut uv_min.y, &mut uv_max.y);
+        }
+
+        let material = Self {
+            params: SpriteOccluderParams {
+                uv_min,
+                uv_max,
+                alpha_threshold: occluder.alpha_threshold,
+            },
+            mask:   mask.clone(),
+        };
+        let transform =
+            Transform::from_translation((-anchor * size).extend(0.0)).with_scale(size.extend(1.0));
+
+        Some((material, transform))
+    }
+}
+
+#[rustfmt::skip]
+pub fn system_setup_sprite_occluders(
+    mut commands:   Commands,
+    mut meshes:     ResMut<Assets<Mesh>>,
+    camera_targets: Res<CameraTargets>,
+) {
+    meshes.insert(
+        OCCLUDER_PROXY_RECT.id(),
+        Mesh::from(bevy::math::primitives::Rectangle::new(1.0, 1.0)),
+    );
+
+    commands.spawn((
+        Name::new("occluder_camera"),
+        OccluderCamera,
+        Camera2d,
+        Camera {
+            order:       -1,
+            target:      RenderTarget::Image(camera_targets.occluder_target.clone()),
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ..default()
+        },
+        Msaa::Off,
+        RenderLayers::from_layers(render_layer::CAMERA_LAYER_OCCLUDER),
+    ));
+}
+
+/// Keeps the occluder camera covering the SDF extent around the floor camera
+/// view. Runs after transform propagation to follow the floor camera within
+/// the same frame.
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+pub fn system_sync_occluder_camera(
+    query_floor_camera:        Query<(&GlobalTransform, &OrthographicProjection), (With<FloorCamera>, Without<OccluderCamera>)>,
+    mut query_occluder_camera: Query<(&mut Transform, &mut GlobalTransform, &mut OrthographicProjection), With<OccluderCamera>>,
+) {
+    let (
+        Ok((floor_transform, floor_projection)),
+        Ok((mut transform, mut global_transform, mut projection)),
+    ) = (query_floor_camera.get_single(), query_occluder_camera.get_single_mut()) else {
+        return;
+    };
+
+    // The SDF is centred on the view, which is off the camera position
+    // when the viewport origin isn't centred.
+    let area = floor_projection.area;
+    if area.is_empty() {
+        return;
+    }
+
+    let view_transform = Transform {
+        translation: floor_transform.transform_point(area.center().extend(0.0)),
+        ..floor_transform.compute_transform()
+    };
+
+    transform.set_if_neq(view_transform);
+    *global_transform = GlobalTransform::from(view_transform);
+
+    projection.near            = floor_projection.near;
+    projection.far             = floor_projection.far;
+    projection.scale           = 1.0;
+    projection.viewport_origin = Vec2::splat(0.5);
+    projection.scaling_mode    = ScalingMode::Fixed {
+        width:  area.width() * SDF_VIEW_SCALE,
+        height: area.height() * SDF_VIEW_SCALE,
+    };
+}
+
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
+pub fn system_sync_sprite_occluders(
+    mut commands:      Commands,
+    mut materials:     ResMut<Assets<SpriteOccluderMaterial>>,
+        images:        Res<Assets<Image>>,
+        atlas_layouts: Res<Assets<TextureAtlasLayout>>,
+
+        query_occluders: Query<(Entity, &SpriteOccluder2D, Option<&Sprite>, Option<&SpriteOccluderProxy>)>,
+    mut query_proxies:   Query<(&MeshMaterial2d<SpriteOccluderMaterial>, &mut Transform, &mut Visibility)>,
+        query_removed:   Query<&SpriteOccluderProxy, Without<SpriteOccluder2D>>,
+
+    mut removed_occluders: RemovedComponents<SpriteOccluder2D>,
+) {
+    for entity in removed_occluders.read() {
+        if let Ok(proxy) = query_removed.get(entity) {
+            commands.entity(proxy.0).despawn();
+            commands.entity(entity).remove::<SpriteOccluderProxy>();
+        }
+    }
+
+    for (entity, occluder, sprite, proxy) in query_occluders.iter() {
+        let Some(proxy) = proxy else {
+            let proxy = commands
+                .spawn((
+                    Mesh2d(OCCLUDER_PROXY_RECT.clone()),
+                    MeshMaterial2d(materials.add(SpriteOccluderMaterial::default())),
+                    Transform::default(),
+                    Visibility::Hidden,
+                    RenderLayers::from_layers(render_layer::CAMERA_LAYER_OCCLUDER),
+                ))
+                .set_parent(entity)
+                .id();
+            commands.entity(entity).insert(SpriteOccluderProxy(proxy));
+            continue;
+        };
+
+        let Ok((material, mut transform, mut visibility)) = query_proxies.get_mut(proxy.0) else {
+            continue;
+        };
+
+        let Some((new_material, new_transform)) =
+            SpriteOccluderMaterial::resolve(occluder, sprite, &images, &atlas_layouts)
+        else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        // Only touch the material when it changes to avoid re-preparing it.
+        if materials
+            .get(&material.0)
+            .is_none_or(|old| old.params != new_material.params || old.mask != new_material.mask)
+        {
+            materials.insert(&material.0, new_material);
+        }
+        transform.set_if_neq(new_transform);
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
+
+/// Resizes occluders whose sprite changed, or whose image finished loading or
+/// was modified.
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+pub fn system_sync_occluders_from_sprite(
+    mut commands:      Commands,
+        images:        Res<Assets<Image>>,
+        atlas_layouts: Res<Assets<TextureAtlasLayout>>,
+    mut image_events:  EventReader<AssetEvent<Image>>,
+
+        query_changed:   Query<Entity, (With<OccluderFromSprite>, Or<(Changed<Sprite>, Changed<OccluderFromSprite>)>)>,
+        query_sprites:   Query<(Entity, &Sprite), With<OccluderFromSprite>>,
+    mut query_occluders: Query<(&OccluderFromSprite, &Sprite, Option<&mut LightOccluder2D>)>,
+) {
+    let loaded = image_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut entities = query_changed.iter().collect::<Vec<_>>();
+    if !loaded.is_empty() {
+        entities.extend(
+            query_sprites
+                .iter()
+                .filter(|(_, sprite)| loaded.contains(&sprite.image.id()))
+                .map(|(entity, _)| entity),
+        );
+    }
+
+    for entity in entities {
+        let Ok((from_sprite, sprite, occluder)) = query_occluders.get_mut(entity) else {
+            continue;
+        };
+        // The image size is only known once it's loaded.
+        let size = match sprite.custom_size {
+            Some(size) => size,
+            None => {
+                let Some(image) = images.get(&sprite.image) else {
+                    continue;
+                };
+                sprite_rect(Some(sprite), image.size_f32(), &atlas_layouts).size()
+            }
+        };
+        let h_size = size * 0.5 * from_sprite.scale;
+
+        match occluder {
+            Some(mut occluder) => {
+                if occluder.h_size != h_size {
+                    occluder.h_size = h_size;
+                }
+            }
+            None => {
+                commands.entity(entity).insert(LightOccluder2D::from(h_size));
+            }
+        }
+    }
+}
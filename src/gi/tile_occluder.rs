@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::render::view::RenderLayers;
+use bevy::sprite::Material2d;
+
+use crate::gi::constants::OCCLUDER_PROXY_RECT;
+use crate::gi::render_layer;
+use crate::gi::types::TileOccluderGrid2D;
+
+/// Quad rendering the solid tiles of a [`TileOccluderGrid2D`] on the occluder
+/// layer.
+#[derive(Component)]
+pub struct TileOccluderProxy(Entity);
+
+#[rustfmt::skip]
+#[derive(AsBindGroup, Clone, TypePath, Asset, Default)]
+pub struct TileOccluderMaterial {
+    #[texture(0)]
+    cells: Handle<Image>,
+}
+
+impl Material2d for TileOccluderMaterial
+{
+    fn fragment_shader() -> ShaderRef
+    {
+        "embedded://bevy_magic_light_2d/gi/shaders/gi_tile_occluder.wgsl".into()
+    }
+}
+
+/// Transform of the quad covering the grid, `None` until the cells image is
+/// loaded.
+fn grid_transform(grid: &TileOccluderGrid2D, images: &Assets<Image>) -> Option<Transform>
+{
+    let extent = images.get(&grid.cells)?.size_f32() * grid.tile_size;
+    Some(
+        Transform::from_translation((grid.origin + extent * 0.5).extend(0.0))
+            .with_scale(extent.extend(1.0)),
+    )
+}
+
+#[rustfmt::skip]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
+pub fn system_sync_tile_occluders(
+    mut commands:     Commands,
+    mut materials:    ResMut<Assets<TileOccluderMaterial>>,
+        images:       Res<Assets<Image>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+
+        query_grids:   Query<(Entity, &TileOccluderGrid2D, Option<&TileOccluderProxy>)>,
+    mut query_proxies: Query<(&MeshMaterial2d<TileOccluderMaterial>, &mut Transform, &mut Visibility)>,
+        query_removed: Query<&TileOccluderProxy, Without<TileOccluderGrid2D>>,
+        query_owners:  Query<(), With<TileOccluderProxy>>,
+        query_meshes:  Query<(Entity, &Parent), With<MeshMaterial2d<TileOccluderMaterial>>>,
+
+    mut removed_grids: RemovedComponents<TileOccluderGrid2D>,
+) {
+    for entity in removed_grids.read() {
+        if let Ok(proxy) = query_removed.get(entity) {
+            commands.entity(proxy.0).despawn_recursive();
+            commands.entity(entity).remove::<TileOccluderProxy>();
+        } else if !query_owners.contains(entity) {
+            // Grids despawned without their children leave their proxy behind.
+            for (proxy, parent) in query_meshes.iter() {
+                if parent.get() == entity {
+                    commands.entity(proxy).despawn_recursive();
+                }
+            }
+        }
+    }
+
+    // Materials keep the texture they were prepared with, edited cells are
+    // only picked up once the material is prepared again.
+    let modified = image_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for (entity, grid, proxy) in query_grids.iter() {
+        let Some(proxy) = proxy else {
+            let proxy = commands
+                .spawn((
+                    Mesh2d(OCCLUDER_PROXY_RECT.clone()),
+                    MeshMaterial2d(materials.add(TileOccluderMaterial::default())),
+                    Transform::default(),
+                    Visibility::Hidden,
+                    RenderLayers::from_layers(render_layer::CAMERA_LAYER_OCCLUDER),
+                ))
+                .set_parent(entity)
+                .id();
+            commands.entity(entity).insert(TileOccluderProxy(proxy));
+            continue;
+        };
+
+        let Ok((material, mut transform, mut visibility)) = query_proxies.get_mut(proxy.0) else {
+            continue;
+        };
+
+        let Some(new_transform) = grid_transform(grid, &images) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        if modified.contains(&grid.cells.id())
+            || materials.get(&material.0).is_none_or(|old| old.cells != grid.cells)
+        {
+            materials.insert(&material.0, TileOccluderMaterial {
+                cells: grid.cells.clone(),
+            });
+        }
+        transform.set_if_neq(new_transform);
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
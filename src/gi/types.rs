@@ -1,21 +1,340 @@
 use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+/// Attenuation of a light as a function of the distance `d` to the emitter.
+/// Every model has a cutoff `radius` past which the light contributes nothing.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub enum LightFalloff
+{
+    /// Inverse square law `(reference / max(d, reference))²`, cut off at
+    /// `radius`.
+    InverseSquare
+    {
+        reference: f32, radius: f32
+    },
+    /// Linear fade from full intensity at the light to zero at `radius`.
+    Linear
+    {
+        radius: f32
+    },
+    /// Inverse square law `reference² / (reference² + d²)`, windowed by
+    /// `(1 - (d / radius)⁴)²` to reach zero smoothly at `radius`.
+    Smooth
+    {
+        reference: f32, radius: f32
+    },
+    /// Rational falloff `a / (b + c * d²)`, cut off at `radius`.
+    Custom
+    {
+        a:      f32,
+        b:      f32,
+        c:      f32,
+        radius: f32,
+    },
+}
+
+impl Default for LightFalloff
+{
+    fn default() -> Self
+    {
+        LightFalloff::Smooth {
+            reference: 32.0,
+            radius:    256.0,
+        }
+    }
+}
+
+impl LightFalloff
+{
+    /// Distance past which the light contributes nothing.
+    pub fn radius(&self) -> f32
+    {
+        match *self {
+            LightFalloff::InverseSquare { radius, .. }
+            | LightFalloff::Linear { radius }
+            | LightFalloff::Smooth { radius, .. }
+            | LightFalloff::Custom { radius, .. } => radius.max(0.0),
+        }
+    }
+}
+
+/// Rational `a / (b + c * d²)` falloff from its `(a, b, c)` coefficients. The
+/// radius is placed where the attenuation drops to 0.1% of its peak, it is
+/// unbounded when the falloff doesn't decay.
+impl From<Vec3> for LightFalloff
+{
+    fn from(value: Vec3) -> Self
+    {
+        let radius = if value.y > 0.0 && value.z > 0.0 {
+            (999.0 * value.y / value.z).sqrt()
+        } else {
+            f32::INFINITY
+        };
+        LightFalloff::Custom {
+            a: value.x,
+            b: value.y,
+            c: value.z,
+            radius,
+        }
+    }
+}
 
 #[rustfmt::skip]
-#[derive(Reflect, Component, Clone, Copy, Default)]
+#[derive(Reflect, Component, Clone, Copy)]
 #[reflect(Component)]
 pub struct OmniLightSource2D {
     pub intensity:          f32,
     pub color:              Color,
-    pub falloff:            Vec3,
+    pub falloff:            LightFalloff,
+    /// Amplitude of the seeded noise flicker added to the intensity.
     pub jitter_intensity:   f32,
+    /// Amplitude of the seeded noise flicker added to the position.
     pub jitter_translation: f32,
+    /// Whether occluders block the light. Fill lights and glows can disable
+    /// it to skip the shadow raymarch.
+    pub cast_shadows:       bool,
+    /// Radius of the emitter, widening the shadow penumbra. Zero casts hard
+    /// shadows.
+    pub source_radius:      f32,
 }
 
+impl Default for OmniLightSource2D
+{
+    fn default() -> Self
+    {
+        Self {
+            intensity:          0.0,
+            color:              Color::default(),
+            falloff:            LightFalloff::default(),
+            jitter_intensity:   0.0,
+            jitter_translation: 0.0,
+            cast_shadows:       true,
+            source_radius:      0.0,
+        }
+    }
+}
+
+/// Light source emitting within a cone. The cone is oriented along the local
+/// `+X` axis of the entity, so its direction follows the entity rotation.
 #[rustfmt::skip]
-#[derive(Reflect, Component, Default, Clone, Copy)]
+#[derive(Reflect, Component, Clone, Copy)]
+#[reflect(Component)]
+pub struct SpotLight2D {
+    pub intensity:     f32,
+    pub color:         Color,
+    pub falloff:       LightFalloff,
+    /// Half-angle (radians) of the cone receiving full intensity.
+    pub inner_angle:   f32,
+    /// Half-angle (radians) of the cone at which the light fades out.
+    pub outer_angle:   f32,
+    /// Whether occluders block the light.
+    pub cast_shadows:  bool,
+    /// Radius of the emitter, widening the shadow penumbra. Zero casts hard
+    /// shadows.
+    pub source_radius: f32,
+}
+
+impl Default for SpotLight2D
+{
+    fn default() -> Self
+    {
+        Self {
+            intensity:     0.0,
+            color:         Color::default(),
+            falloff:       LightFalloff::default(),
+            inner_angle:   std::f32::consts::FRAC_PI_8,
+            outer_angle:   std::f32::consts::FRAC_PI_4,
+            cast_shadows:  true,
+            source_radius: 0.0,
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum LightCookieProjection
+{
+    /// `u` follows the angle around the light, `v` the distance from it.
+    #[default]
+    Polar,
+    /// Cookie is projected as a square centered at the light.
+    Planar,
+}
+
+/// Texture modulating the color of an [`OmniLightSource2D`] or [`SpotLight2D`]
+/// on the same entity. The cookie rotates with the light entity.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Default)]
+#[reflect(Component)]
+pub struct LightCookie2D {
+    pub image:      Handle<Image>,
+    pub projection: LightCookieProjection,
+    /// Radius (polar) or half size (planar) of the projected cookie.
+    pub size:       f32,
+}
+
+/// Light source emitting from a segment centered at the entity and aligned
+/// with its local `+X` axis. The shadow penumbra follows the segment length.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Copy)]
+#[reflect(Component)]
+pub struct LineLight2D {
+    pub intensity:    f32,
+    pub color:        Color,
+    pub falloff:      LightFalloff,
+    pub half_length:  f32,
+    /// Whether occluders block the light.
+    pub cast_shadows: bool,
+}
+
+impl Default for LineLight2D
+{
+    fn default() -> Self
+    {
+        Self {
+            intensity:    0.0,
+            color:        Color::default(),
+            falloff:      LightFalloff::default(),
+            half_length:  0.0,
+            cast_shadows: true,
+        }
+    }
+}
+
+/// Light source emitting from an oriented box centered at the entity. The
+/// shadow penumbra follows the box size.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Copy)]
+#[reflect(Component)]
+pub struct AreaLight2D {
+    pub intensity:    f32,
+    pub color:        Color,
+    pub falloff:      LightFalloff,
+    pub h_size:       Vec2,
+    /// Whether occluders block the light.
+    pub cast_shadows: bool,
+}
+
+impl Default for AreaLight2D
+{
+    fn default() -> Self
+    {
+        Self {
+            intensity:    0.0,
+            color:        Color::default(),
+            falloff:      LightFalloff::default(),
+            h_size:       Vec2::ZERO,
+            cast_shadows: true,
+        }
+    }
+}
+
+/// Shape of an occluder, inscribed in the box of its `h_size`.
+#[derive(Reflect, Clone, Copy, Default, Debug, PartialEq)]
+pub enum LightOccluderShape
+{
+    #[default]
+    Box,
+    /// Circle with the radius of the shorter half size.
+    Circle,
+    /// Capsule along the longer axis, with the radius of the shorter half size.
+    Capsule,
+    /// Box with corners rounded by `radius`.
+    RoundedBox
+    {
+        radius: f32
+    },
+}
+
+/// How an occluder combines with the other occluders in the SDF. Occluders
+/// rasterised with [`SdfMode::JumpFlood`](crate::gi::resource::SdfMode) are
+/// always unioned, and subtractive ones are skipped.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub enum OccluderOperation
+{
+    Union,
+    /// Union blending distances closer than `radius`, so that seams between
+    /// occluders are rounded.
+    SmoothUnion
+    {
+        radius: f32,
+    },
+    /// Carves the occluder out of the other occluders, with edges rounded by
+    /// `radius`. Static subtractive occluders only carve static occluders.
+    Subtract
+    {
+        radius: f32,
+    },
+}
+
+/// Occluders are merged with a small rounding by default, like the SDF pass
+/// always did before operations were configurable.
+impl Default for OccluderOperation
+{
+    fn default() -> Self
+    {
+        OccluderOperation::SmoothUnion { radius: 1.2 }
+    }
+}
+
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Copy)]
 #[reflect(Component)]
 pub struct LightOccluder2D {
-    pub h_size: Vec2,
+    /// Half size in the entity local space, scaled by its [`GlobalTransform`].
+    pub h_size:    Vec2,
+    pub shape:     LightOccluderShape,
+    pub operation: OccluderOperation,
+    /// Colour of the surface, tinting the light bounced off the occluder.
+    pub albedo:    Color,
+    /// Light emitted by the surface, picked up by the bounce pass.
+    pub emissive:  Color,
+}
+
+impl Default for LightOccluder2D
+{
+    fn default() -> Self
+    {
+        Self {
+            h_size:    Vec2::ZERO,
+            shape:     LightOccluderShape::default(),
+            operation: OccluderOperation::default(),
+            albedo:    Color::WHITE,
+            emissive:  Color::BLACK,
+        }
+    }
+}
+
+impl LightOccluder2D
+{
+    pub fn circle(radius: f32) -> Self
+    {
+        Self {
+            h_size: Vec2::splat(radius),
+            shape: LightOccluderShape::Circle,
+            ..default()
+        }
+    }
+
+    /// Capsule aligned with the local `+X` axis, `half_length` excludes the
+    /// rounded caps.
+    pub fn capsule(radius: f32, half_length: f32) -> Self
+    {
+        Self {
+            h_size: Vec2::new(half_length + radius, radius),
+            shape: LightOccluderShape::Capsule,
+            ..default()
+        }
+    }
+
+    pub fn rounded_box(h_size: Vec2, radius: f32) -> Self
+    {
+        Self {
+            h_size,
+            shape: LightOccluderShape::RoundedBox { radius },
+            ..default()
+        }
+    }
 }
 
 impl From<(f32, f32)> for LightOccluder2D
@@ -24,6 +343,7 @@ impl From<(f32, f32)> for LightOccluder2D
     {
         LightOccluder2D {
             h_size: value.into(),
+            ..default()
         }
     }
 }
@@ -32,15 +352,272 @@ impl From<Vec2> for LightOccluder2D
 {
     fn from(value: Vec2) -> Self
     {
-        LightOccluder2D { h_size: value }
+        LightOccluder2D {
+            h_size: value,
+            ..default()
+        }
+    }
+}
+
+impl From<Rectangle> for LightOccluder2D
+{
+    fn from(rectangle: Rectangle) -> Self
+    {
+        rectangle.half_size.into()
+    }
+}
+
+impl From<Circle> for LightOccluder2D
+{
+    fn from(circle: Circle) -> Self
+    {
+        LightOccluder2D::circle(circle.radius)
+    }
+}
+
+/// Capsule aligned with the local `+Y` axis, like [`Capsule2d`].
+impl From<Capsule2d> for LightOccluder2D
+{
+    fn from(capsule: Capsule2d) -> Self
+    {
+        LightOccluder2D {
+            h_size: Vec2::new(capsule.radius, capsule.half_length + capsule.radius),
+            shape: LightOccluderShape::Capsule,
+            ..default()
+        }
+    }
+}
+
+/// Occluder shaped as a closed polygon with vertices in the entity local
+/// space. Both convex and concave polygons are supported, the winding order
+/// doesn't matter.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone)]
+#[reflect(Component)]
+pub struct PolygonOccluder2D {
+    pub vertices:  Vec<Vec2>,
+    pub operation: OccluderOperation,
+    /// Colour of the surface, tinting the light bounced off the occluder.
+    pub albedo:    Color,
+    /// Light emitted by the surface, picked up by the bounce pass.
+    pub emissive:  Color,
+}
+
+impl Default for PolygonOccluder2D
+{
+    fn default() -> Self
+    {
+        Self {
+            vertices:  Vec::new(),
+            operation: OccluderOperation::default(),
+            albedo:    Color::WHITE,
+            emissive:  Color::BLACK,
+        }
+    }
+}
+
+impl From<Vec<Vec2>> for PolygonOccluder2D
+{
+    fn from(vertices: Vec<Vec2>) -> Self
+    {
+        PolygonOccluder2D {
+            vertices,
+            ..default()
+        }
+    }
+}
+
+/// Polygon with a vertex at the top, like [`RegularPolygon`].
+impl From<RegularPolygon> for PolygonOccluder2D
+{
+    fn from(polygon: RegularPolygon) -> Self
+    {
+        polygon.vertices(0.0).into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+/// Occluder shaped as the outline of the entity [`Mesh2d`], read from its
+/// positions and triangle indices. Holes and disjoint parts of the mesh are
+/// kept. Outlines are extracted once per mesh asset and again when the mesh
+/// changes, which must be kept in the main world.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Copy)]
+#[reflect(Component)]
+pub struct MeshOccluder2D {
+    pub operation: OccluderOperation,
+    /// Colour of the surface, tinting the light bounced off the occluder.
+    pub albedo:    Color,
+    /// Light emitted by the surface, picked up by the bounce pass.
+    pub emissive:  Color,
+}
+
+impl Default for MeshOccluder2D
+{
+    fn default() -> Self
+    {
+        Self {
+            operation: OccluderOperation::default(),
+            albedo:    Color::WHITE,
+            emissive:  Color::BLACK,
+        }
+    }
+}
+
+/// Marks a [`LightOccluder2D`], [`PolygonOccluder2D`] or [`MeshOccluder2D`]
+/// that rarely moves. With
+/// [`SdfMode::Analytic`](crate::gi::resource::SdfMode::Analytic) static
+/// occluders are baked into a cached SDF around the view, rebuilt only when a
+/// static occluder changes or the view leaves the cached region.
+#[derive(Reflect, Component, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct StaticOccluder2D;
+
+/// Lets light through a [`LightOccluder2D`], [`PolygonOccluder2D`] or
+/// [`MeshOccluder2D`], like stained glass, foliage or smoke. Light crossing the
+/// occluder is attenuated by `opacity` and multiplied by `tint`, once per
+/// occluder crossed.
+///
+/// Only supported with
+/// [`SdfMode::Analytic`](crate::gi::resource::SdfMode::Analytic), jump flooded
+/// occluders are opaque. Translucent occluders are never cached as static.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Copy)]
+#[reflect(Component)]
+pub struct TranslucentOccluder2D {
+    /// Fraction of the light blocked, `1.0` is opaque.
+    pub opacity: f32,
+    pub tint:    Color,
+}
+
+impl Default for TranslucentOccluder2D
+{
+    fn default() -> Self
+    {
+        Self {
+            opacity: 0.5,
+            tint:    Color::WHITE,
+        }
+    }
+}
+
+/// Occluder rasterised from the alpha of the entity [`Sprite`], or of an
+/// explicit mask image laid out like the sprite image. Texels with alpha above
+/// the threshold block light, so sprites cast pixel-accurate shadows.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone)]
+#[reflect(Component)]
+pub struct SpriteOccluder2D {
+    /// Mask sampled instead of the sprite image.
+    pub mask:            Option<Handle<Image>>,
+    /// Minimum alpha of an occluding texel.
+    pub alpha_threshold: f32,
+}
+
+impl Default for SpriteOccluder2D
+{
+    fn default() -> Self
+    {
+        Self {
+            mask:            None,
+            alpha_threshold: 0.5,
+        }
+    }
+}
+
+/// Grid of solid tiles rasterised as a whole, a tilemap is a single entity
+/// whatever its size. The cells image is drawn on the occluder layer with one
+/// texel per tile and jump flooded into the SDF, so neighbouring solid tiles
+/// merge into continuous walls without seams.
+///
+/// Solid runs are not merged into analytic shapes: the SDF of the grid is a
+/// rasterised approximation, only accurate to an SDF texel, and it lags one
+/// frame behind the grid like other jump flooded occluders.
+///
+/// Texel `(x, y)` of `cells` covers `origin + (x, y) * tile_size` in the entity
+/// local space, the first row of the image is the bottom row of the grid. A
+/// tile is solid when the red channel of its texel is above one half.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Default)]
+#[reflect(Component)]
+pub struct TileOccluderGrid2D {
+    /// Size of a tile in the entity local space.
+    pub tile_size: Vec2,
+    /// Corner of the tile `(0, 0)` in the entity local space.
+    pub origin:    Vec2,
+    pub cells:     Handle<Image>,
+}
+
+impl TileOccluderGrid2D
+{
+    /// Cells image of a `size` grid, `solid` tells whether the tile at the
+    /// given cell blocks light. Keeps a copy in the main world so the grid
+    /// can be edited through [`Image::data`].
+    pub fn cells_image(size: UVec2, solid: impl Fn(UVec2) -> bool) -> Image
+    {
+        let data = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| UVec2::new(x, y)))
+            .map(|cell| if solid(cell) { u8::MAX } else { 0 })
+            .collect();
+        Image::new(
+            Extent3d {
+                width:                 size.x,
+                height:                size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::R8Unorm,
+            RenderAssetUsages::default(),
+        )
     }
 }
 
+/// Sizes the [`LightOccluder2D`] of the entity to its [`Sprite`], from the
+/// sprite `custom_size` or the size of its image or atlas rect. The occluder
+/// is inserted when missing and only its `h_size` is updated otherwise. The
+/// occluder stays centred on the entity whatever the sprite anchor.
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Copy)]
+#[reflect(Component)]
+pub struct OccluderFromSprite {
+    /// Fraction of the sprite size covered by the occluder.
+    pub scale: Vec2,
+}
+
+impl Default for OccluderFromSprite
+{
+    fn default() -> Self
+    {
+        Self { scale: Vec2::ONE }
+    }
+}
+
+/// Shape of a [`SkylightMask2D`], in the entity local space.
+#[derive(Reflect, Clone, Debug, Default, PartialEq)]
+pub enum SkylightMaskShape
+{
+    #[default]
+    Box,
+    /// Circle with the radius of the shorter half size.
+    Circle,
+    /// Closed polygon, convex or concave, the half size is unused.
+    Polygon
+    {
+        vertices: Vec<Vec2>
+    },
+}
+
+/// Area covered by a roof, probes inside receive no skylight nor directional
+/// light. The mask is placed, rotated and scaled by the entity transform.
 #[rustfmt::skip]
-#[derive(Reflect, Component, Default)]
+#[derive(Reflect, Component, Clone, Default)]
 #[reflect(Component)]
 pub struct SkylightMask2D {
-    pub h_size: Vec2,
+    pub h_size:  Vec2,
+    pub shape:   SkylightMaskShape,
+    /// Distance inside the mask over which skylight fades out, so it doesn't
+    /// pop across doorways. `0.0` gives a hard edge.
+    pub feather: f32,
 }
 
 #[rustfmt::skip]
@@ -50,3 +627,33 @@ pub struct SkylightLight2D {
     pub color:     Color,
     pub intensity: f32,
 }
+
+/// Infinitely distant light (e.g. sun or moon) casting parallel shadows.
+/// Like skylight, it does not reach probes covered by [`SkylightMask2D`].
+#[rustfmt::skip]
+#[derive(Reflect, Component, Clone, Copy)]
+#[reflect(Component)]
+pub struct DirectionalLight2D {
+    pub color:         Color,
+    pub intensity:     f32,
+    /// Direction the light travels in, shadows are cast along it.
+    pub direction:     Vec2,
+    /// Maximum distance an occluder can be from a point to shadow it.
+    pub shadow_length: f32,
+    /// Whether occluders block the light.
+    pub cast_shadows:  bool,
+}
+
+impl Default for DirectionalLight2D
+{
+    fn default() -> Self
+    {
+        Self {
+            color:         Color::WHITE,
+            intensity:     0.0,
+            direction:     Vec2::new(-1.0, -1.0),
+            shadow_length: 256.0,
+            cast_shadows:  true,
+        }
+    }
+}
@@ -2,27 +2,121 @@ use bevy::prelude::*;
 use bevy::render::render_resource::ShaderType;
 
 use crate::gi::constants::GI_SCREEN_PROBE_SIZE;
-use crate::gi::types::OmniLightSource2D;
+use crate::gi::light_cookie::LightCookieAtlas;
+use crate::gi::types::{
+    AreaLight2D,
+    DirectionalLight2D,
+    LightCookie2D,
+    LightCookieProjection,
+    LightFalloff,
+    LightOccluder2D,
+    LightOccluderShape,
+    LineLight2D,
+    OccluderOperation,
+    OmniLightSource2D,
+    SkylightMask2D,
+    SkylightMaskShape,
+    SpotLight2D,
+    TranslucentOccluder2D,
+};
+
+#[rustfmt::skip]
+#[derive(Clone, ShaderType)]
+pub struct GpuLightCookie {
+    pub index:      i32,
+    pub projection: u32,
+    pub size:       f32,
+    pub axis:       Vec2,
+}
+
+impl Default for GpuLightCookie
+{
+    fn default() -> Self
+    {
+        Self {
+            index:      -1,
+            projection: 0,
+            size:       1.0,
+            axis:       Vec2::X,
+        }
+    }
+}
+
+impl GpuLightCookie
+{
+    pub fn new(cookie: Option<&LightCookie2D>, atlas: &LightCookieAtlas, axis: Vec2) -> Self
+    {
+        let Some((cookie, layer)) =
+            cookie.and_then(|cookie| Some((cookie, atlas.layer(cookie.image.id())?)))
+        else {
+            return Self::default();
+        };
+
+        Self {
+            index: layer as i32,
+            projection: match cookie.projection {
+                LightCookieProjection::Polar => 0,
+                LightCookieProjection::Planar => 1,
+            },
+            size: cookie.size.max(1e-3),
+            axis,
+        }
+    }
+}
+
+/// Falloff coefficients, interpreted by `light_attenuation` according to
+/// `model`. `params.w` always holds the cutoff radius.
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuLightFalloff {
+    pub params: Vec4,
+    pub model:  u32,
+}
+
+impl From<LightFalloff> for GpuLightFalloff
+{
+    fn from(falloff: LightFalloff) -> Self
+    {
+        let radius = falloff.radius();
+        let (model, params) = match falloff {
+            LightFalloff::InverseSquare { reference, .. } => {
+                (0, Vec4::new(reference.max(1e-3), 0.0, 0.0, radius))
+            }
+            LightFalloff::Linear { .. } => (1, Vec4::new(0.0, 0.0, 0.0, radius)),
+            LightFalloff::Smooth { reference, .. } => {
+                (2, Vec4::new(reference.max(1e-3), 0.0, 0.0, radius))
+            }
+            LightFalloff::Custom { a, b, c, .. } => (3, Vec4::new(a, b, c, radius)),
+        };
+        Self { params, model }
+    }
+}
 
 #[rustfmt::skip]
 #[derive(Default, Clone, ShaderType)]
 pub struct GpuOmniLightSource {
-    pub center:    Vec2,
-    pub intensity: f32,
-    pub color:     Vec3,
-    pub falloff:   Vec3,
+    pub center:        Vec2,
+    pub intensity:     f32,
+    pub color:         Vec3,
+    pub falloff:       GpuLightFalloff,
+    pub cast_shadows:  u32,
+    pub source_radius: f32,
+    pub cookie:        GpuLightCookie,
 }
 
 impl GpuOmniLightSource
 {
-    pub fn new(light: OmniLightSource2D, center: Vec2) -> Self
+    pub fn new(light: OmniLightSource2D, center: Vec2, cookie: GpuLightCookie) -> Self
     {
         let color: Srgba = light.color.into();
         Self {
             center,
             intensity: light.intensity,
             color: color.to_vec3(),
-            falloff: light.falloff,
+            falloff: light.falloff.into(),
+            cast_shadows: light.cast_shadows as u32,
+            source_radius: light.source_radius.max(0.0),
+            cookie,
         }
     }
 }
@@ -35,12 +129,258 @@ pub struct GpuLightSourceBuffer {
     pub data:  Vec<GpuOmniLightSource>,
 }
 
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuSpotLightSource {
+    pub center:        Vec2,
+    pub intensity:     f32,
+    pub color:         Vec3,
+    pub falloff:       GpuLightFalloff,
+    pub direction:     Vec2,
+    pub cos_inner:     f32,
+    pub cos_outer:     f32,
+    pub cast_shadows:  u32,
+    pub source_radius: f32,
+    pub cookie:        GpuLightCookie,
+}
+
+impl GpuSpotLightSource
+{
+    pub fn new(light: SpotLight2D, center: Vec2, direction: Vec2, cookie: GpuLightCookie) -> Self
+    {
+        let color: Srgba = light.color.into();
+        let outer_angle = light.outer_angle.max(0.0);
+        let inner_angle = light.inner_angle.clamp(0.0, outer_angle);
+        Self {
+            center,
+            intensity: light.intensity,
+            color: color.to_vec3(),
+            falloff: light.falloff.into(),
+            direction,
+            cos_inner: inner_angle.cos(),
+            cos_outer: outer_angle.cos(),
+            cast_shadows: light.cast_shadows as u32,
+            source_radius: light.source_radius.max(0.0),
+            cookie,
+        }
+    }
+}
+
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuSpotLightSourceBuffer {
+    pub count: u32,
+    #[size(runtime)]
+    pub data:  Vec<GpuSpotLightSource>,
+}
+
+/// Line and area lights share the same representation: an oriented box with
+/// `h_extent.y == 0` for segments.
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuAreaLightSource {
+    pub center:       Vec2,
+    pub intensity:    f32,
+    pub color:        Vec3,
+    pub falloff:      GpuLightFalloff,
+    pub axis:         Vec2,
+    pub h_extent:     Vec2,
+    pub cast_shadows: u32,
+}
+
+impl GpuAreaLightSource
+{
+    pub fn from_line(light: LineLight2D, center: Vec2, axis: Vec2) -> Self
+    {
+        let color: Srgba = light.color.into();
+        Self {
+            center,
+            intensity: light.intensity,
+            color: color.to_vec3(),
+            falloff: light.falloff.into(),
+            axis,
+            h_extent: Vec2::new(light.half_length.max(0.0), 0.0),
+            cast_shadows: light.cast_shadows as u32,
+        }
+    }
+
+    pub fn from_area(light: AreaLight2D, center: Vec2, axis: Vec2) -> Self
+    {
+        let color: Srgba = light.color.into();
+        Self {
+            center,
+            intensity: light.intensity,
+            color: color.to_vec3(),
+            falloff: light.falloff.into(),
+            axis,
+            h_extent: light.h_size.max(Vec2::ZERO),
+            cast_shadows: light.cast_shadows as u32,
+        }
+    }
+}
+
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuAreaLightSourceBuffer {
+    pub count: u32,
+    #[size(runtime)]
+    pub data:  Vec<GpuAreaLightSource>,
+}
+
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuDirectionalLight {
+    pub color:         Vec3,
+    pub intensity:     f32,
+    pub direction:     Vec2,
+    pub shadow_length: f32,
+    pub cast_shadows:  u32,
+}
+
+impl GpuDirectionalLight
+{
+    pub fn new(light: DirectionalLight2D, direction: Vec2) -> Self
+    {
+        let color: Srgba = light.color.into();
+        Self {
+            color: color.to_vec3(),
+            intensity: light.intensity,
+            direction,
+            shadow_length: light.shadow_length.max(0.0),
+            cast_shadows: light.cast_shadows as u32,
+        }
+    }
+}
+
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuDirectionalLightBuffer {
+    pub count: u32,
+    #[size(runtime)]
+    pub data:  Vec<GpuDirectionalLight>,
+}
+
 #[rustfmt::skip]
 #[derive(Default, Clone, ShaderType)]
 pub struct GpuLightOccluder2D {
     pub center: Vec2,
     pub rotation: Vec4,
     pub h_extent: Vec2,
+    pub shape: u32,
+    /// Corner radius of rounded boxes.
+    pub radius: f32,
+    pub operation: u32,
+    pub blend_radius: f32,
+    /// Packed light let through, `0` for opaque occluders.
+    pub transmission: u32,
+    /// Packed surface colour.
+    pub albedo: u32,
+    pub emissive: Vec3,
+}
+
+/// Operation id and blend radius of an occluder, ids must match
+/// `gi_occluder_shapes.wgsl`.
+pub fn encode_occluder_operation(operation: OccluderOperation) -> (u32, f32)
+{
+    match operation {
+        OccluderOperation::Union => (0, 0.0),
+        OccluderOperation::SmoothUnion { radius } => (1, radius.max(0.0)),
+        OccluderOperation::Subtract { radius } => (2, radius.max(0.0)),
+    }
+}
+
+/// Packs a colour as `rgba8unorm`, read with `unpack4x8unorm`.
+fn pack_unorm_rgb(color: Vec3) -> u32
+{
+    let [r, g, b] = color
+        .to_array()
+        .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u32);
+    r | g << 8 | b << 16
+}
+
+/// Light let through an occluder packed as `rgba8unorm`, `0` for occluders
+/// blocking all light.
+pub fn encode_occluder_transmission(translucent: Option<&TranslucentOccluder2D>) -> u32
+{
+    let Some(translucent) = translucent else {
+        return 0;
+    };
+    let tint: Srgba = translucent.tint.into();
+    pack_unorm_rgb(tint.to_vec3() * (1.0 - translucent.opacity.clamp(0.0, 1.0)))
+}
+
+/// Surface colour of an occluder packed as `rgba8unorm`.
+pub fn encode_occluder_albedo(albedo: Color) -> u32
+{
+    let albedo: Srgba = albedo.into();
+    pack_unorm_rgb(albedo.to_vec3())
+}
+
+/// Light emitted by the surface of an occluder.
+pub fn encode_occluder_emissive(emissive: Color) -> Vec3
+{
+    let emissive: Srgba = emissive.into();
+    emissive.to_vec3().max(Vec3::ZERO)
+}
+
+/// Scale, rotation and translation of a transform in the XY plane. Sprites
+/// often have a zero Z scale, which a full decomposition doesn't handle.
+pub(crate) fn decompose_2d(transform: &GlobalTransform) -> (Vec2, Quat, Vec2)
+{
+    let affine = transform.affine();
+    let x_axis = affine.matrix3.x_axis.truncate();
+    let y_axis = affine.matrix3.y_axis.truncate();
+    (
+        Vec2::new(x_axis.length(), y_axis.length()),
+        Quat::from_rotation_z(x_axis.y.atan2(x_axis.x)),
+        affine.translation.truncate(),
+    )
+}
+
+impl GpuLightOccluder2D
+{
+    pub fn new(
+        occluder: &LightOccluder2D,
+        translucent: Option<&TranslucentOccluder2D>,
+        transform: &GlobalTransform,
+    ) -> Self
+    {
+        // Shapes are scaled along their local axes, circles and capsules keep
+        // the radius of the shorter scaled half size.
+        let (scale, rotation, center) = decompose_2d(transform);
+        let h_extent = (occluder.h_size * scale).max(Vec2::ZERO);
+        let (shape, radius) = match occluder.shape {
+            LightOccluderShape::Box => (0, 0.0),
+            LightOccluderShape::Circle => (1, 0.0),
+            LightOccluderShape::Capsule => (2, 0.0),
+            LightOccluderShape::RoundedBox { radius } => (
+                3,
+                (radius * scale.min_element()).clamp(0.0, h_extent.min_element()),
+            ),
+        };
+        let (operation, blend_radius) = encode_occluder_operation(occluder.operation);
+        Self {
+            center,
+            rotation: rotation.inverse().into(),
+            h_extent,
+            shape,
+            radius,
+            operation,
+            blend_radius,
+            transmission: encode_occluder_transmission(translucent),
+            albedo: encode_occluder_albedo(occluder.albedo),
+            emissive: encode_occluder_emissive(occluder.emissive),
+        }
+    }
+
+    /// Half size of the world space box bounding the occluder.
+    pub fn h_bounds(&self) -> Vec2
+    {
+        let rotation = Quat::from_vec4(self.rotation).inverse();
+        let axis_x = (rotation * Vec3::X).truncate().abs() * self.h_extent.x;
+        let axis_y = (rotation * Vec3::Y).truncate().abs() * self.h_extent.y;
+        axis_x + axis_y
+    }
 }
 
 #[rustfmt::skip]
@@ -51,6 +391,117 @@ pub struct GpuLightOccluderBuffer {
     pub data:  Vec<GpuLightOccluder2D>,
 }
 
+/// Occluders binned per tile of SDF texels. `data` starts with the offset of
+/// each tile list and the end offset, followed by the lists of occluder
+/// indices.
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuOccluderTileBuffer {
+    pub tile_count:   UVec2,
+    /// Distance the SDF is clamped to, tiles list all occluders closer.
+    pub max_distance: f32,
+    #[size(runtime)]
+    pub data:         Vec<u32>,
+}
+
+impl GpuOccluderTileBuffer
+{
+    /// Bins occluders given by their index and inclusive tile range.
+    pub fn build(&mut self, tile_count: UVec2, max_distance: f32, ranges: &[(u32, UVec2, UVec2)])
+    {
+        let tiles = (tile_count.x * tile_count.y) as usize;
+        let tile_index = |x: u32, y: u32| (y * tile_count.x + x) as usize;
+
+        self.tile_count = tile_count;
+        self.max_distance = max_distance;
+        self.data.clear();
+        self.data.resize(tiles + 1, 0);
+
+        // Count occluders per tile, then turn counts into list offsets.
+        for (_, min, max) in ranges {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    self.data[tile_index(x, y) + 1] += 1;
+                }
+            }
+        }
+        self.data[0] = tiles as u32 + 1;
+        for tile in 0..tiles {
+            self.data[tile + 1] += self.data[tile];
+        }
+
+        let mut cursors = self.data[..tiles].to_vec();
+        self.data.resize(self.data[tiles] as usize, 0);
+        for (index, min, max) in ranges {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let cursor = &mut cursors[tile_index(x, y)];
+                    self.data[*cursor as usize] = *index;
+                    *cursor += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Polygon with world-space vertices stored in the occluder vertex buffer.
+/// Polygons with several contours span consecutive entries, the first one
+/// holds the contour count and the bounds of all contours.
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuPolygonOccluder {
+    pub bounds_min:    Vec2,
+    pub bounds_max:    Vec2,
+    pub vertex_offset: u32,
+    pub vertex_count:  u32,
+    pub operation:     u32,
+    pub blend_radius:  f32,
+    pub transmission:  u32,
+    pub albedo:        u32,
+    /// Entries of the polygon from this one, `0` on the following contours.
+    pub contour_count: u32,
+    pub emissive:      Vec3,
+}
+
+impl GpuPolygonOccluder
+{
+    /// Polygon with the given material, its contours are filled in when
+    /// pushed to the occluder buffers.
+    pub fn new(
+        operation: OccluderOperation,
+        albedo: Color,
+        emissive: Color,
+        translucent: Option<&TranslucentOccluder2D>,
+    ) -> Self
+    {
+        let (operation, blend_radius) = encode_occluder_operation(operation);
+        Self {
+            operation,
+            blend_radius,
+            transmission: encode_occluder_transmission(translucent),
+            albedo: encode_occluder_albedo(albedo),
+            emissive: encode_occluder_emissive(emissive),
+            ..default()
+        }
+    }
+}
+
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuPolygonOccluderBuffer {
+    pub count: u32,
+    #[size(runtime)]
+    pub data:  Vec<GpuPolygonOccluder>,
+}
+
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuOccluderVertexBuffer {
+    pub count: u32,
+    #[size(runtime)]
+    pub data:  Vec<Vec2>,
+}
+
 #[rustfmt::skip]
 #[derive(Default, Clone, ShaderType)]
 pub struct GpuCameraParams {
@@ -62,6 +513,31 @@ pub struct GpuCameraParams {
     pub inv_sdf_scale:     Vec2,
 }
 
+/// World region covered by the static occluder SDF cache.
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuStaticSdfParams {
+    pub bounds_min: Vec2,
+    pub bounds_max: Vec2,
+    /// Zero when no static occluder is visible, the cache isn't sampled then.
+    pub enabled:    u32,
+}
+
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuJfaParams {
+    /// Camera the occluder mask was rendered with, used to reproject it.
+    pub mask_view_proj: Mat4,
+    /// Whether mask distances are merged into the analytic SDF or replace it.
+    pub merge_analytic: u32,
+}
+
+#[rustfmt::skip]
+#[derive(Default, Clone, ShaderType)]
+pub struct GpuJfaStepParams {
+    pub step: i32,
+}
+
 #[rustfmt::skip]
 #[derive(Clone, ShaderType, Debug)]
 pub struct GpuLightPassParams {
@@ -78,6 +554,7 @@ pub struct GpuLightPassParams {
     pub indirect_light_contrib:      f32,
     pub indirect_rays_per_sample:    i32,
     pub indirect_rays_radius_factor: f32,
+    pub emissive_intensity:          f32,
 }
 
 impl Default for GpuLightPassParams
@@ -99,6 +576,7 @@ impl Default for GpuLightPassParams
 
             indirect_rays_per_sample:    64,
             indirect_rays_radius_factor: 3.0,
+            emissive_intensity:          1.0,
         }
     }
 }
@@ -134,18 +612,56 @@ impl Default for GpuProbeDataBuffer
     }
 }
 
+/// Skylight mask in world space, polygon vertices are stored in the skylight
+/// mask vertex buffer.
 #[rustfmt::skip]
 #[derive(Clone, ShaderType, Default)]
 pub struct GpuSkylightMaskData {
-    pub center:   Vec2,
-    pub h_extent: Vec2,
+    pub center:        Vec2,
+    pub rotation:      Vec4,
+    pub h_extent:      Vec2,
+    pub shape:         u32,
+    pub feather:       f32,
+    pub vertex_offset: u32,
+    pub vertex_count:  u32,
 }
 
 impl GpuSkylightMaskData
 {
-    pub fn new(center: Vec2, h_extent: Vec2) -> Self
+    /// Mask placed by `transform`, polygon vertices are pushed to
+    /// `mask_vertices` in world space.
+    pub fn new(
+        mask: &SkylightMask2D,
+        transform: &GlobalTransform,
+        mask_vertices: &mut GpuOccluderVertexBuffer,
+    ) -> Self
     {
-        Self { center, h_extent }
+        // Circles keep the radius of the shorter scaled half size, like
+        // occluders.
+        let (scale, rotation, center) = decompose_2d(transform);
+        let shape = match &mask.shape {
+            SkylightMaskShape::Box => 0,
+            SkylightMaskShape::Circle => 1,
+            SkylightMaskShape::Polygon { .. } => 2,
+        };
+        let vertex_offset = mask_vertices.data.len();
+        if let SkylightMaskShape::Polygon { vertices } = &mask.shape {
+            mask_vertices.data.extend(
+                vertices
+                    .iter()
+                    .map(|vertex| transform.transform_point(vertex.extend(0.0)).truncate()),
+            );
+            mask_vertices.count = mask_vertices.data.len() as u32;
+        }
+        Self {
+            center,
+            rotation: rotation.inverse().into(),
+            h_extent: (mask.h_size * scale).max(Vec2::ZERO),
+            shape,
+            feather: mask.feather.max(0.0),
+            vertex_offset: vertex_offset as u32,
+            vertex_count: (mask_vertices.data.len() - vertex_offset) as u32,
+        }
     }
 }
 
@@ -156,3 +672,50 @@ pub struct GpuSkylightMaskBuffer {
     #[size(runtime)]
     pub data: Vec<GpuSkylightMaskData>,
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    /// Occluder indices listed by a tile.
+    fn tile_occluders(tiles: &GpuOccluderTileBuffer, x: u32, y: u32) -> &[u32]
+    {
+        let tile = (y * tiles.tile_count.x + x) as usize;
+        &tiles.data[tiles.data[tile] as usize..tiles.data[tile + 1] as usize]
+    }
+
+    #[test]
+    fn occluder_tiles_list_straddling_occluder_in_both_tiles()
+    {
+        let mut tiles = GpuOccluderTileBuffer::default();
+        // Occluder 3 straddles the edge between tiles (0, 0) and (1, 0),
+        // occluder 5 only covers tile (1, 1).
+        tiles.build(
+            UVec2::new(2, 2),
+            8.0,
+            &[
+                (3, UVec2::new(0, 0), UVec2::new(1, 0)),
+                (5, UVec2::new(1, 1), UVec2::new(1, 1)),
+            ],
+        );
+
+        assert_eq!(tiles.tile_count, UVec2::new(2, 2));
+        assert_eq!(tiles.max_distance, 8.0);
+        assert_eq!(tile_occluders(&tiles, 0, 0), &[3]);
+        assert_eq!(tile_occluders(&tiles, 1, 0), &[3]);
+        assert_eq!(tile_occluders(&tiles, 0, 1), &[] as &[u32]);
+        assert_eq!(tile_occluders(&tiles, 1, 1), &[5]);
+        assert_eq!(tiles.data.len(), 5 + 3);
+    }
+
+    #[test]
+    fn occluder_tiles_are_rebuilt_from_scratch()
+    {
+        let mut tiles = GpuOccluderTileBuffer::default();
+        tiles.build(UVec2::new(2, 1), 8.0, &[(0, UVec2::ZERO, UVec2::new(1, 0))]);
+        tiles.build(UVec2::ZERO, 0.0, &[]);
+
+        assert_eq!(tiles.data, vec![1]);
+    }
+}
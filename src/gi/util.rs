@@ -1,5 +1,6 @@
 use bevy::asset::AssetPath;
-use bevy::math::IVec2;
+use bevy::math::{IVec2, Vec2};
+use bevy::utils::{HashMap, HashSet};
 
 use crate::gi::WORKGROUP_SIZE;
 
@@ -42,3 +43,214 @@ pub fn align_to_work_group_grid(size: IVec2) -> IVec2
         },
     )
 }
+
+/// Triangulates a simple polygon by ear clipping, returning indices into
+/// `vertices`. Either winding order is accepted, triangles are counter
+/// clockwise. Self-intersecting polygons are only partially covered.
+pub fn triangulate_polygon(vertices: &[Vec2]) -> Vec<u32>
+{
+    if vertices.len() < 3 {
+        return vec![];
+    }
+
+    let signed_area: f32 = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum();
+
+    let mut remaining: Vec<usize> = (0..vertices.len()).collect();
+    if signed_area < 0.0 {
+        remaining.reverse();
+    }
+
+    let is_inside = |p: Vec2, a: Vec2, b: Vec2, c: Vec2| {
+        (b - a).perp_dot(p - a) >= 0.0
+            && (c - b).perp_dot(p - b) >= 0.0
+            && (a - c).perp_dot(p - c) >= 0.0
+    };
+
+    let mut indices = Vec::with_capacity((vertices.len() - 2) * 3);
+    let mut i = 0;
+    let mut misses = 0;
+    while remaining.len() > 3 && misses < remaining.len() {
+        let len = remaining.len();
+        let (prev, curr, next) = (
+            remaining[(i + len - 1) % len],
+            remaining[i],
+            remaining[(i + 1) % len],
+        );
+        let (a, b, c) = (vertices[prev], vertices[curr], vertices[next]);
+
+        let is_ear = (b - a).perp_dot(c - b) > 0.0
+            && !remaining
+                .iter()
+                .filter(|k| ![prev, curr, next].contains(k))
+                .any(|k| is_inside(vertices[*k], a, b, c));
+
+        if is_ear {
+            indices.extend([prev, curr, next].map(|k| k as u32));
+            remaining.remove(i);
+            i %= remaining.len();
+            misses = 0;
+        } else {
+            i = (i + 1) % len;
+            misses += 1;
+        }
+    }
+
+    if remaining.len() == 3 {
+        indices.extend(remaining.iter().map(|k| *k as u32));
+    }
+
+    indices
+}
+
+/// Outline of a triangle mesh as closed contours, made of the edges of a
+/// single triangle. Vertices sharing a position are merged, so meshes with
+/// split vertices are handled. Triangles must be consistently wound, contours
+/// then follow their winding and holes the opposite one.
+pub fn mesh_outlines(positions: &[Vec2], triangles: &[[usize; 3]]) -> Vec<Vec<Vec2>>
+{
+    let mut welded = HashMap::new();
+    let ids: Vec<usize> = positions
+        .iter()
+        .map(|p| {
+            let id = welded.len();
+            // Adding zero turns negative zeros positive.
+            *welded
+                .entry((*p + 0.0).to_array().map(f32::to_bits))
+                .or_insert(id)
+        })
+        .collect();
+    let mut points = vec![Vec2::ZERO; welded.len()];
+    for (p, id) in positions.iter().zip(&ids) {
+        points[*id] = *p;
+    }
+
+    let mut edges = HashSet::new();
+    for triangle in triangles {
+        let [a, b, c] = triangle.map(|i| ids[i]);
+        if a != b && b != c && c != a {
+            edges.extend([(a, b), (b, c), (c, a)]);
+        }
+    }
+
+    // Inner edges are walked the other way by the neighbouring triangle.
+    let mut next: HashMap<usize, Vec<usize>> = HashMap::new();
+    for &(a, b) in edges.iter() {
+        if !edges.contains(&(b, a)) {
+            next.entry(a).or_default().push(b);
+        }
+    }
+
+    let mut starts: Vec<usize> = next.keys().copied().collect();
+    starts.sort_unstable();
+
+    let mut outlines = vec![];
+    for start in starts {
+        while let Some(mut current) = next.get_mut(&start).and_then(Vec::pop) {
+            let mut contour = vec![points[start]];
+            while current != start {
+                contour.push(points[current]);
+                // Open chains only come from inconsistently wound triangles.
+                let Some(following) = next.get_mut(&current).and_then(Vec::pop) else {
+                    break;
+                };
+                current = following;
+            }
+            if contour.len() >= 3 {
+                outlines.push(contour);
+            }
+        }
+    }
+
+    outlines
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn signed_area(contour: &[Vec2]) -> f32
+    {
+        let n = contour.len();
+        (0..n)
+            .map(|i| contour[i].perp_dot(contour[(i + 1) % n]))
+            .sum::<f32>()
+            * 0.5
+    }
+
+    fn sorted_areas(outlines: &[Vec<Vec2>]) -> Vec<f32>
+    {
+        let mut areas: Vec<f32> = outlines.iter().map(|c| signed_area(c)).collect();
+        areas.sort_by(f32::total_cmp);
+        areas
+    }
+
+    #[test]
+    fn mesh_outlines_welds_split_vertices()
+    {
+        // Two triangles of a quad without shared indices.
+        let positions = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ];
+        let outlines = mesh_outlines(&positions, &[[0, 1, 2], [3, 4, 5]]);
+
+        assert_eq!(outlines.len(), 1);
+        assert_eq!(outlines[0].len(), 4);
+        assert_eq!(signed_area(&outlines[0]), 1.0);
+    }
+
+    #[test]
+    fn mesh_outlines_winds_holes_opposite()
+    {
+        // Square annulus, the ring is made of one quad per side.
+        let outer = [
+            Vec2::new(-2.0, -2.0),
+            Vec2::new(2.0, -2.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(-2.0, 2.0),
+        ];
+        let positions: Vec<Vec2> = outer
+            .iter()
+            .chain(outer.map(|v| v * 0.5).iter())
+            .copied()
+            .collect();
+        let triangles: Vec<[usize; 3]> = (0..4)
+            .flat_map(|i| {
+                let j = (i + 1) % 4;
+                [[i, j, j + 4], [i, j + 4, i + 4]]
+            })
+            .collect();
+        let outlines = mesh_outlines(&positions, &triangles);
+
+        assert_eq!(outlines.len(), 2);
+        assert!(outlines.iter().all(|contour| contour.len() == 4));
+        assert_eq!(sorted_areas(&outlines), vec![-4.0, 16.0]);
+    }
+
+    #[test]
+    fn mesh_outlines_splits_disjoint_parts()
+    {
+        let positions = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(12.0, 0.0),
+            Vec2::new(10.0, 2.0),
+        ];
+        let outlines = mesh_outlines(&positions, &[[0, 1, 2], [3, 4, 5]]);
+
+        assert_eq!(outlines.len(), 2);
+        assert!(outlines.iter().all(|contour| contour.len() == 3));
+        assert_eq!(sorted_areas(&outlines), vec![0.5, 2.0]);
+    }
+}
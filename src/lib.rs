@@ -11,3 +11,8 @@ pub struct FloorCamera;
 pub struct WallsCamera;
 #[derive(Component)]
 pub struct ObjectsCamera;
+#[derive(Component)]
+pub struct EmissiveCamera;
+/// Camera spawned by the plugin to render sprite occluder masks.
+#[derive(Component)]
+pub struct OccluderCamera;
@@ -1,6 +1,41 @@
 pub use crate::gi::compositing::{setup_post_processing_camera, CameraTargets};
-pub use crate::gi::render_layer::{CAMERA_LAYER_FLOOR, CAMERA_LAYER_OBJECTS, CAMERA_LAYER_WALLS};
-pub use crate::gi::resource::{BevyMagicLight2DSettings, LightPassParams};
-pub use crate::gi::types::{LightOccluder2D, OmniLightSource2D, SkylightLight2D, SkylightMask2D};
+pub use crate::gi::light_animation::{LightAnimation2D, LightColorTrack, LightCurve, LightTrack};
+pub use crate::gi::render_layer::{
+    CAMERA_LAYER_EMISSIVE,
+    CAMERA_LAYER_FLOOR,
+    CAMERA_LAYER_OBJECTS,
+    CAMERA_LAYER_WALLS,
+};
+pub use crate::gi::resource::{BevyMagicLight2DSettings, LightPassParams, SdfMode};
+pub use crate::gi::types::{
+    AreaLight2D,
+    DirectionalLight2D,
+    LightCookie2D,
+    LightCookieProjection,
+    LightFalloff,
+    LightOccluder2D,
+    LightOccluderShape,
+    LineLight2D,
+    MeshOccluder2D,
+    OccluderFromSprite,
+    OccluderOperation,
+    OmniLightSource2D,
+    PolygonOccluder2D,
+    SkylightLight2D,
+    SkylightMask2D,
+    SkylightMaskShape,
+    SpotLight2D,
+    SpriteOccluder2D,
+    StaticOccluder2D,
+    TileOccluderGrid2D,
+    TranslucentOccluder2D,
+};
 pub use crate::gi::BevyMagicLight2DPlugin;
-pub use crate::{FloorCamera, ObjectsCamera, SpriteCamera, WallsCamera};
+pub use crate::{
+    EmissiveCamera,
+    FloorCamera,
+    ObjectsCamera,
+    OccluderCamera,
+    SpriteCamera,
+    WallsCamera,
+};